@@ -0,0 +1,252 @@
+use crate::bitboard::*;
+use fen::{Piece, PieceKind};
+use std::sync::OnceLock;
+
+// Sliding attacks are looked up in precomputed tables. The relevant blockers
+// of a square are mapped to a table index either by multiplying with a magic
+// number or, on CPUs with BMI2, by the PEXT instruction.
+
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080004008801020,
+    0x0840092002c03000,
+    0x1900200010400900,
+    0x0880100008000480,
+    0x4200100420080200,
+    0x8100020100080400,
+    0x0200040110886200,
+    0x0200008040220411,
+    0x0404800084400220,
+    0x0000401000402000,
+    0x0086001081220440,
+    0x0408800800100280,
+    0x000a001201040820,
+    0x8848800200840080,
+    0x4001000100040200,
+    0x0442000102105084,
+    0x9080010020804100,
+    0x0040404000201009,
+    0x0000808010002009,
+    0x2200090021d00100,
+    0x0008008008040080,
+    0x0004004002010040,
+    0x0011040008015042,
+    0x00000a0001768104,
+    0x0000800080204009,
+    0x2010004140002001,
+    0x9800200280100080,
+    0x1000100080080080,
+    0x0442000a00049020,
+    0x2100040080020080,
+    0x0800120400900148,
+    0x0010040a00128541,
+    0x2800804000800030,
+    0x1010002000400041,
+    0x4000200011004100,
+    0x0610008410800800,
+    0x0400802402800800,
+    0xc100020080800400,
+    0x0002000802000401,
+    0x0182085882000401,
+    0x0220204000808000,
+    0x2860100040024022,
+    0x0001002004110040,
+    0x99101042000a0020,
+    0x0004080004008080,
+    0x0010040002008080,
+    0x2012004881020004,
+    0x8300842444820011,
+    0x0088403882010200,
+    0x0820400080210100,
+    0x0110910040a00300,
+    0x0801100280080480,
+    0x0242009008200600,
+    0x1002000489500200,
+    0x0040800200010080,
+    0x0091800041000080,
+    0x0000209300488001,
+    0x04c1002414824001,
+    0x020020000b001041,
+    0x7000100004200901,
+    0x8002002004100802,
+    0x30010002084c0007,
+    0x0888221800813004,
+    0x4000002840840112,
+];
+const BISHOP_MAGICS: [u64; 64] = [
+    0xa010041108003100,
+    0x006082020a002900,
+    0x6810010619200000,
+    0x08281a0520000408,
+    0x0001104001000400,
+    0x0018901008048400,
+    0x00040a0210245280,
+    0x000200210808a402,
+    0x9140048410821200,
+    0x0800091010820041,
+    0x20504804832202c0,
+    0x0100091401081000,
+    0x8021011140000012,
+    0x0810020804450400,
+    0x208b0542109008a2,
+    0x0080084a08040204,
+    0x0040e2a80811244c,
+    0x2505022008008108,
+    0x0430220100420040,
+    0x010a040420220040,
+    0x1105000290400000,
+    0x0093001200822120,
+    0x4000a62048043004,
+    0x280120048a015004,
+    0x006090002a020814,
+    0x44042000240800d0,
+    0x01102800040a4400,
+    0x1004080080220040,
+    0x0001001011004024,
+    0x0010044000805040,
+    0x0914041200820100,
+    0x0004821012821480,
+    0x0024040500c05021,
+    0x0088611002080200,
+    0x0116080a00040020,
+    0x4000020080080080,
+    0x2450450140840040,
+    0x0000880201484100,
+    0x0222020404020092,
+    0x8081110600002e00,
+    0x2842101105000801,
+    0x1100809008001025,
+    0x00020202221c0400,
+    0x0422014022009020,
+    0x0210046102100c00,
+    0xc004008082029102,
+    0x00aa461801101200,
+    0x0404080080201108,
+    0x020542108c205002,
+    0x0410544804100100,
+    0x0040910841100000,
+    0x0400200042021100,
+    0x00004204850400c0,
+    0x0200100410a42102,
+    0x1040020801210102,
+    0x0805040410420000,
+    0x2884804130100200,
+    0x800c262201242000,
+    0x1058000194108800,
+    0x0014221054420204,
+    0x0104000012a02200,
+    0x0200881003300100,
+    0x0140400202840100,
+    0x0402020801010201,
+];
+
+// magic and shift are unused when the index is computed with PEXT
+#[cfg_attr(all(target_arch = "x86_64", target_feature = "bmi2"), allow(dead_code))]
+struct Magic {
+    // relevant blocker squares, the board edges are never relevant
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    // start of this square's attacks in SlidingTables::attacks
+    offset: usize,
+}
+
+impl Magic {
+    #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+    fn index(&self, occupancy: Bitboard) -> usize {
+        // SAFETY: the bmi2 target feature is enabled at compile time
+        let index = unsafe { std::arch::x86_64::_pext_u64(occupancy, self.mask) };
+        return self.offset + index as usize;
+    }
+
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+    fn index(&self, occupancy: Bitboard) -> usize {
+        let index = (occupancy & self.mask).wrapping_mul(self.magic) >> self.shift;
+        return self.offset + index as usize;
+    }
+}
+
+struct SlidingTables {
+    rook: Vec<Magic>,
+    bishop: Vec<Magic>,
+    attacks: Vec<Bitboard>,
+}
+
+static TABLES: OnceLock<SlidingTables> = OnceLock::new();
+
+fn tables() -> &'static SlidingTables {
+    TABLES.get_or_init(|| {
+        let mut attacks = Vec::new();
+        let rook = build_magics(&ROOK_DIRECTIONS, &ROOK_MAGICS, &mut attacks);
+        let bishop = build_magics(&BISHOP_DIRECTIONS, &BISHOP_MAGICS, &mut attacks);
+        SlidingTables {
+            rook,
+            bishop,
+            attacks,
+        }
+    })
+}
+
+// the squares along the directions which can block the ray, a piece on the
+// last square before the edge never blocks anything
+fn relevant_blockers(square: usize, directions: &[(i32, i32)]) -> Bitboard {
+    let rank_edges = (RANK_1 | RANK_8) & !(RANK_1 << (8 * (square / 8)));
+    let file_edges = (FILE_A | FILE_H) & !(FILE_A << (square % 8));
+    return sliding_attacks(square, EMPTY, directions) & !(rank_edges | file_edges);
+}
+
+fn build_magics(
+    directions: &[(i32, i32)],
+    magics: &[u64; 64],
+    attacks: &mut Vec<Bitboard>,
+) -> Vec<Magic> {
+    let mut table = Vec::with_capacity(64);
+    for (square, magic) in magics.iter().enumerate() {
+        let mask = relevant_blockers(square, directions);
+        let entry = Magic {
+            mask,
+            magic: *magic,
+            shift: 64 - mask.count_ones(),
+            offset: attacks.len(),
+        };
+        attacks.resize(attacks.len() + (1 << mask.count_ones()), EMPTY);
+        // walk all subsets of the mask (carry rippler)
+        let mut occupancy = EMPTY;
+        loop {
+            attacks[entry.index(occupancy)] = sliding_attacks(square, occupancy, directions);
+            occupancy = occupancy.wrapping_sub(mask) & mask;
+            if occupancy == EMPTY {
+                break;
+            }
+        }
+        table.push(entry);
+    }
+    return table;
+}
+
+pub fn rook_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    let tables = tables();
+    return tables.attacks[tables.rook[square].index(occupancy)];
+}
+
+pub fn bishop_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    let tables = tables();
+    return tables.attacks[tables.bishop[square].index(occupancy)];
+}
+
+pub fn queen_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    return rook_attacks(square, occupancy) | bishop_attacks(square, occupancy);
+}
+
+// All squares the piece standing on the given square attacks when the
+// squares in occupancy are occupied. Pawns only attack diagonally,
+// the squares they can push to are not included.
+pub fn attacks_from(square: usize, piece: &Piece, occupancy: Bitboard) -> Bitboard {
+    match piece.kind {
+        PieceKind::Pawn => PAWN_ATTACKS[color_index(&piece.color)][square],
+        PieceKind::Knight => KNIGHT_ATTACKS[square],
+        PieceKind::Bishop => bishop_attacks(square, occupancy),
+        PieceKind::Rook => rook_attacks(square, occupancy),
+        PieceKind::Queen => queen_attacks(square, occupancy),
+        PieceKind::King => KING_ATTACKS[square],
+    }
+}
@@ -0,0 +1,128 @@
+// Counts the leaf nodes of the legal move tree of a position to compare the
+// move generator against other engines.
+//
+//     chesslib-perft "<fen>" <depth> [--divide] [--threads <n>]
+//
+// With --divide the node count below every legal move is printed in the
+// same "e2e4: 1234" format most engines use.
+
+#![allow(clippy::needless_return)]
+
+use chesslib::move_generation::MoveGeneration;
+use chesslib::move_utils::Move;
+use chesslib::position::Position;
+use chesslib::STARTING_POSITION;
+use std::process::exit;
+use std::time::Instant;
+
+const USAGE: &str = "usage: chesslib-perft <fen|startpos> <depth> [--divide] [--threads <n>]";
+
+struct Options {
+    fen: String,
+    depth: usize,
+    divide: bool,
+    threads: usize,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut divide = false;
+    let mut threads = 1;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--divide" => divide = true,
+            "--threads" => {
+                threads = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or("--threads expects a positive number")?;
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+    if positional.len() != 2 {
+        return Err(USAGE.to_string());
+    }
+    let fen = match positional[0].as_str() {
+        "startpos" => STARTING_POSITION.to_string(),
+        fen => fen.to_string(),
+    };
+    let depth = positional[1]
+        .parse()
+        .map_err(|_| format!("invalid depth {}", positional[1]))?;
+    return Ok(Options {
+        fen,
+        depth,
+        divide,
+        threads,
+    });
+}
+
+// perft of every root move, the root moves are split evenly across threads
+// and the result is sorted by the long algebraic notation of the moves
+fn divide(position: &Position, depth: usize, threads: usize) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let moves = position.legal_moves();
+    let chunk_size = moves.len().div_ceil(threads).max(1);
+    let mut nodes: Vec<(Move, u64)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = moves
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut position = position.clone();
+                    let mut nodes = Vec::new();
+                    for mov in chunk {
+                        let undo = position.make_move(mov);
+                        nodes.push((mov.clone(), position.perft(depth - 1)));
+                        position.unmake_move(undo);
+                    }
+                    nodes
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    nodes.sort_by_key(|(mov, _)| mov.to_long_algebraic());
+    return nodes;
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = parse_args(&args).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        exit(1);
+    });
+    let position = Position::try_from_fen(&options.fen).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+
+    let start = Instant::now();
+    let nodes_per_move = divide(&position, options.depth, options.threads);
+    let nodes = if options.depth == 0 {
+        1
+    } else {
+        nodes_per_move.iter().map(|(_, nodes)| nodes).sum()
+    };
+    let elapsed = start.elapsed();
+
+    if options.divide {
+        for (mov, nodes) in &nodes_per_move {
+            println!("{}: {}", mov.to_long_algebraic(), nodes);
+        }
+        println!();
+    }
+    println!("Nodes searched: {}", nodes);
+    println!("Time: {} ms", elapsed.as_millis());
+    println!(
+        "Nodes per second: {}",
+        (nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64
+    );
+}
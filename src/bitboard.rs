@@ -0,0 +1,156 @@
+use fen::{Color, PieceKind};
+
+// A set of squares, bit n being set means square n is part of the set.
+// Squares are indexed the same way as in fen::BoardState, a1 = 0, h8 = 63
+pub type Bitboard = u64;
+
+pub const EMPTY: Bitboard = 0;
+pub const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+pub const FILE_H: Bitboard = FILE_A << 7;
+pub const RANK_1: Bitboard = 0xff;
+pub const RANK_8: Bitboard = RANK_1 << 56;
+// a1 and every square of the same color
+pub const DARK_SQUARES: Bitboard = 0xaa55_aa55_aa55_aa55;
+
+pub const ROOK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, -1), (-1, 0), (0, 1)];
+pub const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (2, 1),
+    (2, -1),
+    (-2, 1),
+    (-2, -1),
+    (1, 2),
+    (1, -2),
+    (-1, 2),
+    (-1, -2),
+];
+const KING_OFFSETS: [(i32, i32); 8] = [
+    (1, 0),
+    (0, -1),
+    (-1, 0),
+    (0, 1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+// all squares a knight on the given square attacks
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+// all squares a king on the given square attacks
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_OFFSETS);
+// all squares a pawn on the given square attacks, indexed by color first
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[(1, 1), (-1, 1)]),
+    leaper_attacks(&[(1, -1), (-1, -1)]),
+];
+
+const fn on_board(rank: i32, file: i32) -> bool {
+    0 <= rank && rank < 8 && 0 <= file && file < 8
+}
+
+const fn leaper_attacks(offsets: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut table = [EMPTY; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let file = (square % 8) as i32 + offsets[i].0;
+            let rank = (square / 8) as i32 + offsets[i].1;
+            if on_board(rank, file) {
+                table[square] |= 1 << (rank * 8 + file);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    return table;
+}
+
+// Attacks of a sliding piece moving along the given directions. Every ray
+// stops at the first occupied square, which is itself part of the attacks.
+pub fn sliding_attacks(square: usize, occupancy: Bitboard, directions: &[(i32, i32)]) -> Bitboard {
+    let mut attacks = EMPTY;
+    for (file_inc, rank_inc) in directions {
+        let mut file = (square % 8) as i32;
+        let mut rank = (square / 8) as i32;
+        loop {
+            file += file_inc;
+            rank += rank_inc;
+            if !on_board(rank, file) {
+                break;
+            }
+            let target = square_bb((rank * 8 + file) as usize);
+            attacks |= target;
+            if occupancy & target != 0 {
+                break;
+            }
+        }
+    }
+    return attacks;
+}
+
+pub fn square_bb(square: usize) -> Bitboard {
+    return 1 << square;
+}
+
+pub fn contains(bitboard: Bitboard, square: usize) -> bool {
+    return bitboard & square_bb(square) != 0;
+}
+
+// Iterate over the squares contained in a bitboard from a1 towards h8
+pub fn squares(bitboard: Bitboard) -> Squares {
+    return Squares(bitboard);
+}
+
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == EMPTY {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        return Some(square);
+    }
+}
+
+pub fn color_index(color: &Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+pub fn color_from_index(index: usize) -> Color {
+    match index {
+        0 => Color::White,
+        _ => Color::Black,
+    }
+}
+
+pub fn kind_index(kind: &PieceKind) -> usize {
+    match kind {
+        PieceKind::Pawn => 0,
+        PieceKind::Knight => 1,
+        PieceKind::Bishop => 2,
+        PieceKind::Rook => 3,
+        PieceKind::Queen => 4,
+        PieceKind::King => 5,
+    }
+}
+
+pub fn kind_from_index(index: usize) -> PieceKind {
+    match index {
+        0 => PieceKind::Pawn,
+        1 => PieceKind::Knight,
+        2 => PieceKind::Bishop,
+        3 => PieceKind::Rook,
+        4 => PieceKind::Queen,
+        _ => PieceKind::King,
+    }
+}
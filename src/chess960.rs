@@ -0,0 +1,80 @@
+use crate::position::Position;
+use fen::PieceKind;
+
+// The 960 starting positions of Fischer Random Chess, numbered as by
+// Scharnagl. Number 518 is the standard starting position.
+
+pub const POSITIONS: usize = 960;
+
+// the knight squares among the five squares left after bishops and queen
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+// The pieces on the first rank from the a- to the h-file
+pub fn back_rank(index: usize) -> Option<[PieceKind; 8]> {
+    if index >= POSITIONS {
+        return None;
+    }
+    let mut rank: [Option<PieceKind>; 8] = Default::default();
+    let mut n = index;
+    // one bishop on a light, the other on a dark square
+    rank[2 * (n % 4) + 1] = Some(PieceKind::Bishop);
+    n /= 4;
+    rank[2 * (n % 4)] = Some(PieceKind::Bishop);
+    n /= 4;
+    let empty = |rank: &[Option<PieceKind>; 8]| -> Vec<usize> {
+        (0..8).filter(|file| rank[*file].is_none()).collect()
+    };
+    rank[empty(&rank)[n % 6]] = Some(PieceKind::Queen);
+    n /= 6;
+    let free = empty(&rank);
+    let (first, second) = KNIGHT_PLACEMENTS[n];
+    rank[free[first]] = Some(PieceKind::Knight);
+    rank[free[second]] = Some(PieceKind::Knight);
+    // the king goes between the rooks on the three squares left
+    for (file, kind) in
+        empty(&rank)
+            .into_iter()
+            .zip([PieceKind::Rook, PieceKind::King, PieceKind::Rook])
+    {
+        rank[file] = Some(kind);
+    }
+    return Some(rank.map(|kind| kind.unwrap()));
+}
+
+// The starting position in X-FEN, e.g. "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+pub fn starting_fen(index: usize) -> Option<String> {
+    let rank: String = back_rank(index)?
+        .iter()
+        .map(|kind| match kind {
+            PieceKind::Pawn => 'p',
+            PieceKind::Knight => 'n',
+            PieceKind::Bishop => 'b',
+            PieceKind::Rook => 'r',
+            PieceKind::Queen => 'q',
+            PieceKind::King => 'k',
+        })
+        .collect();
+    return Some(format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+        rank,
+        rank.to_ascii_uppercase()
+    ));
+}
+
+impl Position {
+    pub fn chess960_start(index: usize) -> Option<Self> {
+        let fen = starting_fen(index)?;
+        return Some(Self::from_chess960_fen(&fen).expect("generated FEN is valid"));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::bitboard::color_index;
+use crate::move_utils::ToggleColor;
+use fen::Color;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Chess clocks for timed games. A Clock counts down the time of the player
+// on the move according to a TimeControl and reads the time from a
+// TimeSource, which tests replace by a ManualTime they advance by hand.
+
+pub trait TimeSource {
+    // time passed since some fixed point, it must never decrease
+    fn now(&self) -> Duration;
+}
+
+// The time of the system's monotonic clock
+pub struct RealTime {
+    start: Instant,
+}
+
+impl RealTime {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for RealTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for RealTime {
+    fn now(&self) -> Duration {
+        return self.start.elapsed();
+    }
+}
+
+// Time that only moves when told to. Clones share the same time, so a test
+// can keep one to advance the time of the clock it gave the other to.
+#[derive(Clone, Default)]
+pub struct ManualTime {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualTime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        return *self.now.lock().unwrap();
+    }
+}
+
+// What a player gets on top of the base time for every move
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimeBonus {
+    None,
+    // added after every move
+    Fischer(Duration),
+    // the time used for a move is given back, up to the delay
+    Bronstein(Duration),
+    // the clock only starts counting down once the delay has passed
+    SimpleDelay(Duration),
+}
+
+// A period of the game, e.g. 90 minutes for the first 40 moves
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeControlStage {
+    // moves to be made in this stage, None for the rest of the game
+    pub moves: Option<u32>,
+    // added to the remaining time when the stage starts
+    pub time: Duration,
+    pub bonus: TimeBonus,
+}
+
+// The stages are played in order. If the last stage has a number of moves
+// it is repeated, like "40 moves in 2 hours" for the whole game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    // never empty, the clock starts with the time of the first stage
+    stages: Vec<TimeControlStage>,
+}
+
+impl TimeControl {
+    // A time control of the given stages, None if there are none
+    pub fn new(stages: Vec<TimeControlStage>) -> Option<Self> {
+        if stages.is_empty() {
+            return None;
+        }
+        return Some(Self { stages });
+    }
+
+    pub fn stages(&self) -> &[TimeControlStage] {
+        return &self.stages;
+    }
+
+    pub fn sudden_death(time: Duration) -> Self {
+        return Self::single_stage(time, TimeBonus::None);
+    }
+
+    pub fn fischer(time: Duration, increment: Duration) -> Self {
+        return Self::single_stage(time, TimeBonus::Fischer(increment));
+    }
+
+    pub fn bronstein(time: Duration, delay: Duration) -> Self {
+        return Self::single_stage(time, TimeBonus::Bronstein(delay));
+    }
+
+    pub fn simple_delay(time: Duration, delay: Duration) -> Self {
+        return Self::single_stage(time, TimeBonus::SimpleDelay(delay));
+    }
+
+    // The given time for every `moves` moves, e.g. 40/120 for 40 moves in
+    // 2 hours, then another 2 hours for the next 40 and so on
+    pub fn moves_to_go(moves: u32, time: Duration) -> Self {
+        return Self {
+            stages: vec![TimeControlStage {
+                moves: Some(moves),
+                time,
+                bonus: TimeBonus::None,
+            }],
+        };
+    }
+
+    fn single_stage(time: Duration, bonus: TimeBonus) -> Self {
+        return Self {
+            stages: vec![TimeControlStage {
+                moves: None,
+                time,
+                bonus,
+            }],
+        };
+    }
+}
+
+// The state of one player's side of the clock
+#[derive(Clone, Debug)]
+struct Side {
+    remaining: Duration,
+    stage: usize,
+    moves_in_stage: u32,
+}
+
+#[derive(Clone)]
+pub struct Clock {
+    control: TimeControl,
+    time_source: Arc<dyn TimeSource + Send + Sync>,
+    sides: [Side; 2],
+    // the player whose time is running and the time the move started at
+    running: Option<(Color, Duration)>,
+    // the player whose time ran out
+    flagged: Option<Color>,
+}
+
+impl Clock {
+    // A stopped clock with the time of the first stage on both sides.
+    // The time control has to have at least one stage.
+    pub fn new(control: TimeControl, time_source: Arc<dyn TimeSource + Send + Sync>) -> Self {
+        let side = Side {
+            remaining: control.stages[0].time,
+            stage: 0,
+            moves_in_stage: 0,
+        };
+        return Self {
+            control,
+            time_source,
+            sides: [side.clone(), side],
+            running: None,
+            flagged: None,
+        };
+    }
+
+    // Starts the time of the given player, stopping the other one's
+    pub fn start(&mut self, color: Color) {
+        if self.flagged.is_none() {
+            self.running = Some((color, self.time_source.now()));
+        }
+    }
+
+    // Stops the clock, the time used for the current move is kept
+    pub fn stop(&mut self) {
+        if let Some((color, _)) = self.running.clone() {
+            let remaining = self.remaining(&color);
+            self.sides[color_index(&color)].remaining = remaining;
+            self.running = None;
+            if remaining == Duration::ZERO {
+                self.flagged = Some(color);
+            }
+        }
+    }
+
+    pub fn running(&self) -> Option<Color> {
+        return self.running.as_ref().map(|(color, _)| color.clone());
+    }
+
+    // Ends the move of the player whose time is running and starts the
+    // opponent's time. Returns false if the time ran out before the move
+    // was completed, the clock then stays stopped.
+    pub fn press(&mut self) -> bool {
+        let (color, started) = match self.running.clone() {
+            Some(running) => running,
+            None => return self.flagged.is_none(),
+        };
+        let now = self.time_source.now();
+        let used = now.saturating_sub(started);
+        let bonus = self.stage(&color).bonus.clone();
+        let side = &mut self.sides[color_index(&color)];
+        let counted = match bonus {
+            TimeBonus::SimpleDelay(delay) => used.saturating_sub(delay),
+            _ => used,
+        };
+        if counted >= side.remaining {
+            side.remaining = Duration::ZERO;
+            self.running = None;
+            self.flagged = Some(color);
+            return false;
+        }
+        side.remaining -= counted;
+        match bonus {
+            TimeBonus::Fischer(increment) => side.remaining += increment,
+            TimeBonus::Bronstein(delay) => side.remaining += used.min(delay),
+            TimeBonus::None | TimeBonus::SimpleDelay(_) => (),
+        }
+
+        side.moves_in_stage += 1;
+        let stage_moves = self.control.stages[side.stage].moves;
+        if stage_moves == Some(side.moves_in_stage) {
+            // the last stage is repeated if it has a number of moves
+            side.stage = (side.stage + 1).min(self.control.stages.len() - 1);
+            side.moves_in_stage = 0;
+            side.remaining += self.control.stages[side.stage].time;
+        }
+        self.running = Some((color.inverse_color(), now));
+        return true;
+    }
+
+    // The time left on the player's clock, counting the move in progress
+    pub fn remaining(&self, color: &Color) -> Duration {
+        let side = &self.sides[color_index(color)];
+        let started = match &self.running {
+            Some((running, started)) if running == color => *started,
+            _ => return side.remaining,
+        };
+        let mut used = self.time_source.now().saturating_sub(started);
+        if let TimeBonus::SimpleDelay(delay) = self.stage(color).bonus {
+            used = used.saturating_sub(delay);
+        }
+        return side.remaining.saturating_sub(used);
+    }
+
+    // Moves the player has to make before the next time control, None if
+    // the current stage lasts until the end of the game
+    pub fn moves_to_go(&self, color: &Color) -> Option<u32> {
+        let side = &self.sides[color_index(color)];
+        return self
+            .stage(color)
+            .moves
+            .map(|moves| moves - side.moves_in_stage);
+    }
+
+    // The player whose flag fell, if any
+    pub fn flag(&self) -> Option<Color> {
+        if self.flagged.is_some() {
+            return self.flagged.clone();
+        }
+        let (color, _) = self.running.as_ref()?;
+        if self.remaining(color) == Duration::ZERO {
+            return Some(color.clone());
+        }
+        return None;
+    }
+
+    fn stage(&self, color: &Color) -> &TimeControlStage {
+        return &self.control.stages[self.sides[color_index(color)].stage];
+    }
+}
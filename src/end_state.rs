@@ -1,47 +1,322 @@
-use crate::move_generation::*;
+use crate::bitboard::*;
 use crate::move_utils::ToggleColor;
+use crate::pgn::GameResult;
+use crate::position::Position;
 use crate::Game;
 use fen::{Color, PieceKind};
 
+// Why a game ended in a draw or why a player may claim one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DrawReason {
+    Stalemate,
+    InsufficientMaterial,
+    // 50 moves of each player without a capture or pawn move, claimable
+    FiftyMoveRule,
+    // 75 moves of each player without a capture or pawn move, automatic
+    SeventyFiveMoveRule,
+    // the same position occurred three times, claimable
+    ThreefoldRepetition,
+    // the same position occurred five times, automatic
+    FivefoldRepetition,
+}
+
+// Whether the game goes on and if not, how it ended. The colors are those
+// of the winner.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate(Color),
+    // the enemy king was blown up in atomic chess
+    KingExploded(Color),
+    Stalemate,
+    Insufficient,
+    // the seventy-five move rule
+    FiftyMove,
+    // the fivefold repetition
+    Repetition,
+    Resignation(Color),
+    // None if the player whose time ran out can not be mated
+    Timeout(Option<Color>),
+    Agreement,
+}
+
+impl GameStatus {
+    // the value of the PGN Result tag
+    pub fn result(&self) -> GameResult {
+        match self {
+            Self::Ongoing => GameResult::Unknown,
+            Self::Checkmate(winner)
+            | Self::KingExploded(winner)
+            | Self::Resignation(winner)
+            | Self::Timeout(Some(winner)) => match winner {
+                Color::White => GameResult::WhiteWins,
+                Color::Black => GameResult::BlackWins,
+            },
+            Self::Stalemate
+            | Self::Insufficient
+            | Self::FiftyMove
+            | Self::Repetition
+            | Self::Timeout(None)
+            | Self::Agreement => GameResult::Draw,
+        }
+    }
+
+    // the value of the PGN Termination tag
+    pub fn termination(&self) -> &'static str {
+        match self {
+            Self::Ongoing => "unterminated",
+            Self::Timeout(_) => "time forfeit",
+            _ => "normal",
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        return *self != Self::Ongoing;
+    }
+}
+
+impl Game {
+    // How the game stands, the legal moves are only generated once. A game
+    // ended by resignation, agreement or a recorded flag fall stays ended, a
+    // result on the board counts before a flag that fell afterwards.
+    pub fn status(&self) -> GameStatus {
+        if let Some(status) = &self.ended {
+            return status.clone();
+        }
+        if let Some(status) = self.board_outcome() {
+            return status;
+        }
+        if self.board.halfmove_clock() >= 150 {
+            return GameStatus::FiftyMove;
+        }
+        if self.draw_by_repetition {
+            return GameStatus::Repetition;
+        }
+        if let Some(flagged) = self.clock.as_ref().and_then(|clock| clock.flag()) {
+            return self.timeout(&flagged);
+        }
+        return GameStatus::Ongoing;
+    }
+
+    // The flag of the given player fell, the opponent wins unless their
+    // material can not mate
+    pub(crate) fn timeout(&self, flagged: &Color) -> GameStatus {
+        let opponent = flagged.inverse_color();
+        if can_not_mate(&self.board, &opponent) {
+            return GameStatus::Timeout(None);
+        }
+        return GameStatus::Timeout(Some(opponent));
+    }
+
+    // the end of the game by the rules of the variant, see Variant::outcome
+    fn board_outcome(&self) -> Option<GameStatus> {
+        return self.variant.outcome(&self.board, &self.legal_moves());
+    }
+}
+
 pub trait StateCheck {
     fn current_player_in_check(&self) -> bool;
     fn current_player_is_checkmate(&self) -> bool;
     fn current_player_is_stalemate(&self) -> bool;
     fn winner(&self) -> Option<Color>;
     fn is_draw(&self) -> bool;
+    // the reason the game is drawn, claimable draws are not included
+    fn draw_reason(&self) -> Option<DrawReason>;
+    // a draw the player on the move may claim although the game goes on
+    fn claimable_draw(&self) -> Option<DrawReason>;
+    fn can_claim_fifty_move_draw(&self) -> bool;
+    // how often the current position occurred in the game, counting itself
+    fn repetition_count(&self) -> usize;
+    fn can_claim_threefold(&self) -> bool;
     fn insufficient_material(&self) -> bool;
+    // no sequence of legal moves can lead to a mate, which besides
+    // insufficient material covers pawn chains that lock out both kings
+    fn is_dead_position(&self) -> bool;
 }
 
 impl StateCheck for Game {
     fn current_player_in_check(&self) -> bool {
-        let mut simulation_board = self.board.duplicate();
-        simulation_board.side_to_play = Color::inverse_color(&simulation_board.side_to_play);
-        return simulation_board.player_in_check();
+        return self.variant.in_check(&self.board);
     }
     fn current_player_is_checkmate(&self) -> bool {
-        return self.board.legal_moves().len() == 0 && self.current_player_in_check();
+        return matches!(self.board_outcome(), Some(GameStatus::Checkmate(_)));
     }
     fn current_player_is_stalemate(&self) -> bool {
-        return self.board.legal_moves().len() == 0 && !self.current_player_in_check();
+        return self.board_outcome() == Some(GameStatus::Stalemate);
     }
     fn insufficient_material(&self) -> bool {
-        for square in &self.board.pieces {
-            if square.clone().is_some_and(|p| p.kind != PieceKind::King) {
-                return false;
+        return insufficient_material(&self.board);
+    }
+    fn is_dead_position(&self) -> bool {
+        return self.insufficient_material() || is_locked_pawn_position(&self.board);
+    }
+    fn winner(&self) -> Option<Color> {
+        return match self.board_outcome() {
+            Some(GameStatus::Checkmate(winner)) | Some(GameStatus::KingExploded(winner)) => {
+                Some(winner)
             }
+            _ => None,
+        };
+    }
+    fn is_draw(&self) -> bool {
+        return self.draw_reason().is_some();
+    }
+    fn draw_reason(&self) -> Option<DrawReason> {
+        match self.board_outcome() {
+            Some(GameStatus::Stalemate) => return Some(DrawReason::Stalemate),
+            Some(GameStatus::Insufficient) => return Some(DrawReason::InsufficientMaterial),
+            // a checkmate on the 150th half move still wins
+            Some(_) => return None,
+            None => (),
         }
-        return true;
+        if self.board.halfmove_clock() >= 150 {
+            return Some(DrawReason::SeventyFiveMoveRule);
+        }
+        if self.draw_by_repetition {
+            return Some(DrawReason::FivefoldRepetition);
+        }
+        return None;
     }
-    fn winner(&self) -> Option<Color> {
-        if !self.current_player_is_checkmate() {
-            return None;
+    fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.can_claim_fifty_move_draw() {
+            return Some(DrawReason::FiftyMoveRule);
         }
-        return Some(Color::inverse_color(&self.board.side_to_play));
+        if self.can_claim_threefold() {
+            return Some(DrawReason::ThreefoldRepetition);
+        }
+        return None;
     }
-    fn is_draw(&self) -> bool {
-        return self.board.fullmove_number >= 100
-            || self.insufficient_material()
-            || self.current_player_is_stalemate()
-            || self.draw_by_repetition;
+    fn can_claim_fifty_move_draw(&self) -> bool {
+        return self.board.halfmove_clock() >= 100;
+    }
+    fn repetition_count(&self) -> usize {
+        return self
+            .previous_positions
+            .get(&self.variant.repetition_key(&self.board))
+            .copied()
+            .unwrap_or(0);
+    }
+    fn can_claim_threefold(&self) -> bool {
+        return self.repetition_count() >= 3;
+    }
+}
+
+// K vs K, K+N vs K and kings with any number of bishops that all stand on
+// squares of the same color
+pub fn insufficient_material(board: &Position) -> bool {
+    let pieces_of_kind =
+        |kind: &PieceKind| board.pieces(&Color::White, kind) | board.pieces(&Color::Black, kind);
+    return only_minor_pieces(
+        board.occupied(),
+        pieces_of_kind(&PieceKind::King),
+        pieces_of_kind(&PieceKind::Knight),
+        pieces_of_kind(&PieceKind::Bishop),
+    );
+}
+
+// The player has a bare king, a king and a knight or a king and bishops
+// on squares of one color, which can not mate on its own. Used for flag
+// falls, where the opponent's own pieces are not counted as helpers.
+pub fn can_not_mate(board: &Position, color: &Color) -> bool {
+    return only_minor_pieces(
+        board.occupied_by(color),
+        board.pieces(color, &PieceKind::King),
+        board.pieces(color, &PieceKind::Knight),
+        board.pieces(color, &PieceKind::Bishop),
+    );
+}
+
+// The pieces are kings and at most a single knight or bishops that all
+// stand on squares of the same color
+fn only_minor_pieces(
+    pieces: Bitboard,
+    kings: Bitboard,
+    knights: Bitboard,
+    bishops: Bitboard,
+) -> bool {
+    if pieces != kings | knights | bishops {
+        return false;
+    }
+    if knights != EMPTY {
+        return bishops == EMPTY && knights.count_ones() == 1;
     }
+    return bishops & DARK_SQUARES == EMPTY || bishops & !DARK_SQUARES == EMPTY;
+}
+
+// The player whose king is the only one left on the board in atomic chess
+pub fn exploded_king_winner(board: &Position) -> Option<Color> {
+    let white_king = board.king_square(&Color::White);
+    let black_king = board.king_square(&Color::Black);
+    return match (white_king, black_king) {
+        (Some(_), None) => Some(Color::White),
+        (None, Some(_)) => Some(Color::Black),
+        _ => None,
+    };
+}
+
+// In atomic chess kings can not capture, so bare kings or a single knight
+// or bishop can never blow up a king or mate it
+pub fn atomic_insufficient_material(board: &Position) -> bool {
+    let kings = board.pieces(&Color::White, &PieceKind::King)
+        | board.pieces(&Color::Black, &PieceKind::King);
+    let others = board.occupied() & !kings;
+    if others == EMPTY {
+        return true;
+    }
+    let minors = [Color::White, Color::Black]
+        .iter()
+        .fold(EMPTY, |all, color| {
+            all | board.pieces(color, &PieceKind::Knight) | board.pieces(color, &PieceKind::Bishop)
+        });
+    return others.count_ones() == 1 && others & minors != EMPTY;
+}
+
+// Only kings and pawns are left, every pawn is blocked by an enemy pawn,
+// no pawn can capture and neither king can reach an enemy pawn it could
+// take. The pawns can then never move again and the kings alone can not
+// mate.
+fn is_locked_pawn_position(board: &Position) -> bool {
+    let white_pawns = board.pieces(&Color::White, &PieceKind::Pawn);
+    let black_pawns = board.pieces(&Color::Black, &PieceKind::Pawn);
+    let kings = board.pieces(&Color::White, &PieceKind::King)
+        | board.pieces(&Color::Black, &PieceKind::King);
+    if white_pawns == EMPTY
+        || board.occupied() != kings | white_pawns | black_pawns
+        || board.en_passant_square().is_some()
+        || (white_pawns << 8) & !black_pawns != EMPTY
+        || (black_pawns >> 8) & !white_pawns != EMPTY
+    {
+        return false;
+    }
+    for color in [Color::White, Color::Black] {
+        let opponent = color.inverse_color();
+        let own_pawns = board.pieces(&color, &PieceKind::Pawn);
+        let enemy_pawns = board.pieces(&opponent, &PieceKind::Pawn);
+        if pawn_attacks(own_pawns, &color) & board.occupied_by(&opponent) != EMPTY {
+            return false;
+        }
+        // every square the king can ever walk to, squares attacked by enemy
+        // pawns are off limits as those pawns never move
+        let passable = !own_pawns & !pawn_attacks(enemy_pawns, &opponent);
+        let mut reachable = board.pieces(&color, &PieceKind::King);
+        loop {
+            let next = squares(reachable).fold(reachable, |all, square| {
+                all | KING_ATTACKS[square] & passable
+            });
+            if next == reachable {
+                break;
+            }
+            reachable = next;
+        }
+        if reachable & enemy_pawns != EMPTY {
+            return false;
+        }
+    }
+    return true;
+}
+
+fn pawn_attacks(pawns: Bitboard, color: &Color) -> Bitboard {
+    return squares(pawns).fold(EMPTY, |all, square| {
+        all | PAWN_ATTACKS[color_index(color)][square]
+    });
 }
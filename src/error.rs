@@ -0,0 +1,64 @@
+// Errors for input that does not describe a valid position or move,
+// returned by the try_ constructors instead of panicking
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    InvalidFen(String),
+    // a square name that is not a file a-h followed by a rank 1-8
+    InvalidSquare(String),
+    // move text that can not be read as a move at all, e.g. "e2e" or "e7e8k"
+    MalformedMove(String),
+    // a well formed move that is not legal in the position
+    IllegalMove(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidFen(fen) => write!(f, "invalid FEN: {}", fen),
+            Self::InvalidSquare(square) => write!(f, "invalid square: {}", square),
+            Self::MalformedMove(mov) => write!(f, "malformed move: {}", mov),
+            Self::IllegalMove(mov) => write!(f, "illegal move: {}", mov),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Why Game::try_play refused to play a move
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IllegalMove {
+    // there is no piece on the start square
+    EmptySquare,
+    // the piece on the start square belongs to the player not on the move
+    NotYourTurn,
+    CapturesOwnPiece,
+    // the piece can not move from the start to the end square
+    InvalidMovement,
+    // a castling move while the right is lost, the way is blocked or the
+    // king is in check or would pass an attacked square
+    CannotCastle,
+    // a pawn move to the last rank without a promotion piece or a
+    // promotion piece given for any other move
+    InvalidPromotion,
+    LeavesKingInCheck,
+    // the game already ended
+    GameOver,
+}
+
+impl std::fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            Self::EmptySquare => "there is no piece on the start square",
+            Self::NotYourTurn => "the piece belongs to the opponent",
+            Self::CapturesOwnPiece => "a piece can not capture a piece of its own color",
+            Self::InvalidMovement => "the piece can not move like that",
+            Self::CannotCastle => "castling is not possible",
+            Self::InvalidPromotion => "invalid promotion",
+            Self::LeavesKingInCheck => "the move leaves the king in check",
+            Self::GameOver => "the game is over",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+impl std::error::Error for IllegalMove {}
@@ -1,66 +1,382 @@
-use crate::move_utils::Move;
+// explicit returns are used throughout the crate
+#![allow(clippy::needless_return)]
+
+use crate::clock::Clock;
+use crate::end_state::GameStatus;
+use crate::move_utils::{Move, ToggleColor};
+use crate::pgn::GameResult;
+use crate::position::{Position, Undo};
+use crate::square_utils::{file, rank};
+use crate::variant::{Standard, Variant};
+pub use error::{Error, IllegalMove};
 pub use fen::*;
 use std::collections::HashMap;
+use std::sync::Arc;
 
+pub mod attacks;
+pub mod bitboard;
+pub mod chess960;
+pub mod clock;
 pub mod end_state;
+pub mod error;
 pub mod move_generation;
 pub mod move_utils;
+pub mod perft;
+pub mod pgn;
+pub mod position;
+pub mod san;
 pub mod square_utils;
+pub mod variant;
+pub mod zobrist;
 
+#[derive(Clone)]
 pub struct Game {
-    pub board: BoardState,
-    pub previous_positions: HashMap<String, usize>,
+    // This used to be a fen::BoardState, code that still needs one can
+    // get it from Game::board_state
+    pub board: Position,
+    // number of times each position occurred, keyed by Variant::repetition_key
+    pub previous_positions: HashMap<u64, usize>,
+    // set once a position occurred five times
     pub draw_by_repetition: bool,
+    // PGN tag pairs like Event or White, written out by Game::to_pgn
+    pub tags: Vec<(String, String)>,
+    start_position: Position,
+    history: Vec<Move>,
+    // the undo records of the moves in history, in the same order
+    undos: Vec<Undo>,
+    // moves taken back with Game::undo, the next move to redo is last
+    redo_stack: Vec<Move>,
+    // the player who offered a draw the opponent has not answered yet
+    draw_offer: Option<Color>,
+    // how the game ended off the board, by resignation, agreement or on time
+    ended: Option<GameStatus>,
+    // pressed after every move played with Game::execute_move or Game::try_play
+    pub clock: Option<Clock>,
+    variant: Arc<dyn Variant>,
+}
+
+// What happened on the board when Game::try_play played a move
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoveOutcome {
+    // the legal move that was played, with the en passant and castling flags set
+    pub played: Move,
+    pub captured: Option<Piece>,
+    pub check: bool,
+    pub checkmate: bool,
+    pub draw: bool,
 }
 
+pub const STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 impl Game {
     // Generate a new game from the standard starting position
     pub fn new() -> Self {
-        Self::start_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+        Self::start_from_fen(STARTING_POSITION)
     }
 
     // Generate a new game starting from the given fen state
     pub fn start_from_fen(fen: &str) -> Self {
-        Self {
-            board: BoardState::from_fen(fen).expect("Game can't be constructed from invalid fen"),
-            previous_positions: HashMap::from([(
-                fen.to_string()
-                    .split_whitespace()
-                    .take(2)
-                    .collect::<Vec<_>>()
-                    .join(" "),
-                1,
-            )]),
+        Self::try_from_fen(fen).expect("Game can't be constructed from invalid fen")
+    }
+
+    pub fn try_from_fen(fen: &str) -> Result<Self, Error> {
+        return Self::try_variant_from_fen(Arc::new(Standard), fen);
+    }
+
+    // Generate a new game of the variant from its starting position
+    pub fn with_variant(variant: Arc<dyn Variant>) -> Self {
+        let board = variant.starting_position();
+        return Self::variant_from_position(variant, board);
+    }
+
+    pub fn try_variant_from_fen(variant: Arc<dyn Variant>, fen: &str) -> Result<Self, Error> {
+        let board = variant.position_from_fen(fen)?;
+        return Ok(Self::variant_from_position(variant, board));
+    }
+
+    // A Chess960 game from one of the 960 starting positions, see chess960::back_rank
+    pub fn start_chess960(index: usize) -> Option<Self> {
+        return Position::chess960_start(index).map(Self::from_position);
+    }
+
+    pub fn from_position(board: Position) -> Self {
+        return Self::variant_from_position(Arc::new(Standard), board);
+    }
+
+    pub fn variant_from_position(variant: Arc<dyn Variant>, board: Position) -> Self {
+        return Self {
+            previous_positions: HashMap::from([(variant.repetition_key(&board), 1)]),
+            start_position: board.clone(),
+            board,
             draw_by_repetition: false,
+            tags: Vec::new(),
+            history: Vec::new(),
+            undos: Vec::new(),
+            redo_stack: Vec::new(),
+            draw_offer: None,
+            ended: None,
+            clock: None,
+            variant,
+        };
+    }
+
+    pub fn variant(&self) -> &dyn Variant {
+        return self.variant.as_ref();
+    }
+
+    // The current position as the fen::BoardState that Game::board was
+    // before it became a Position
+    pub fn board_state(&self) -> BoardState {
+        return BoardState::from(&self.board);
+    }
+
+    // The legal moves in the current position by the rules of the variant
+    pub fn legal_moves(&self) -> Vec<Move> {
+        return self.variant.legal_moves(&self.board);
+    }
+
+    // Plays the move if it is legal. The move is matched by its start, end,
+    // promotion and dropped piece, the en passant and castling flags are
+    // taken from the legal move.
+    pub fn try_play(&mut self, mov: &Move) -> Result<MoveOutcome, IllegalMove> {
+        if mov.start_square >= 64 || mov.end_square >= 64 {
+            return Err(IllegalMove::InvalidMovement);
+        }
+        let status = self.status();
+        if status.is_over() {
+            // a flag that fell is recorded, later moves can not change the result
+            if let GameStatus::Timeout(_) = status {
+                self.ended = Some(status);
+            }
+            return Err(IllegalMove::GameOver);
+        }
+        let legal_moves = self.legal_moves();
+        let same_squares = |other: &&Move| {
+            other.start_square == mov.start_square
+                && other.end_square == mov.end_square
+                && other.drop == mov.drop
+        };
+        let played = match legal_moves
+            .iter()
+            .filter(same_squares)
+            .find(|other| other.promotion == mov.promotion)
+        {
+            Some(played) => played.clone(),
+            None if legal_moves.iter().any(|other| same_squares(&other)) => {
+                return Err(IllegalMove::InvalidPromotion)
+            }
+            None => return Err(self.illegal_move_reason(mov)),
+        };
+
+        let captured = if played.is_en_passant {
+            let captured_square = rank(played.start_square) * 8 + file(played.end_square);
+            self.board.piece_at(captured_square)
+        } else {
+            self.board.piece_at(played.end_square)
+        };
+        self.execute_move(&played);
+        let status = self.status();
+        return Ok(MoveOutcome {
+            played,
+            captured,
+            check: self.variant.in_check(&self.board),
+            checkmate: matches!(status, GameStatus::Checkmate(_)),
+            draw: status.result() == GameResult::Draw,
+        });
+    }
+
+    // Why a move that is not among the legal moves can not be played
+    fn illegal_move_reason(&self, mov: &Move) -> IllegalMove {
+        let pseudo_legal_moves = self.variant.pseudo_legal_moves(&self.board);
+        if mov.drop.is_some() {
+            // the piece is in the pocket and the square is free
+            if pseudo_legal_moves.contains(mov) {
+                return IllegalMove::LeavesKingInCheck;
+            }
+            return IllegalMove::InvalidMovement;
+        }
+        let piece = match self.board.piece_at(mov.start_square) {
+            Some(piece) => piece,
+            None => return IllegalMove::EmptySquare,
+        };
+        if piece.color != self.board.side_to_play() {
+            return IllegalMove::NotYourTurn;
         }
+        let target = self.board.piece_at(mov.end_square);
+        // castling is written as a king move of two files or, in Chess960,
+        // as the king capturing its own rook
+        let is_castling = piece.kind == PieceKind::King
+            && rank(mov.start_square) == rank(mov.end_square)
+            && (file(mov.start_square).abs_diff(file(mov.end_square)) == 2
+                || (self.board.is_chess960()
+                    && target
+                        == Some(Piece {
+                            kind: PieceKind::Rook,
+                            color: piece.color.clone(),
+                        })));
+        if is_castling {
+            return IllegalMove::CannotCastle;
+        }
+        if target.is_some_and(|target| target.color == piece.color) {
+            return IllegalMove::CapturesOwnPiece;
+        }
+        let pseudo_legal = pseudo_legal_moves.into_iter().any(|other| {
+            other.start_square == mov.start_square && other.end_square == mov.end_square
+        });
+        if pseudo_legal {
+            return IllegalMove::LeavesKingInCheck;
+        }
+        return IllegalMove::InvalidMovement;
     }
 
+    // Plays the move without checking that it is legal, moves that were
+    // undone can no longer be redone. If the time ran out before the move
+    // was completed the game is lost on time, whatever the move did.
     pub fn execute_move(&mut self, mov: &Move) {
-        mov.execute(&mut self.board);
-        let position = self
-            .board
-            .to_fen()
-            .split_whitespace()
-            .take(2)
-            .collect::<Vec<_>>()
-            .join(" ");
-        let mut new_val: usize = 1;
-        if let Some(&val) = self.previous_positions.get(&position) {
-            new_val = val + 1;
-            if new_val >= 3 {
-                self.draw_by_repetition = true;
+        self.redo_stack.clear();
+        self.push_move(mov);
+        let flagged = match &mut self.clock {
+            Some(clock) => {
+                if clock.press() {
+                    None
+                } else {
+                    clock.flag()
+                }
             }
+            None => None,
+        };
+        if let (Some(flagged), None) = (flagged, &self.ended) {
+            self.ended = Some(self.timeout(&flagged));
         }
-        self.previous_positions.insert(position, new_val);
     }
-}
 
-impl Clone for Game {
-    fn clone(&self) -> Self {
-        Self {
-            board: BoardState::from_fen(&self.board.to_fen()).unwrap(),
-            previous_positions: self.previous_positions.clone(),
-            draw_by_repetition: self.draw_by_repetition,
+    // Plays the game on the clock, the time of the player on the move
+    // starts running. Undoing and redoing moves does not touch the clock.
+    pub fn set_clock(&mut self, mut clock: Clock) {
+        clock.start(self.board.side_to_play());
+        self.clock = Some(clock);
+    }
+
+    fn push_move(&mut self, mov: &Move) {
+        // moving instead of accepting declines the opponent's offer
+        if self
+            .draw_offer
+            .as_ref()
+            .is_some_and(|color| *color != self.board.side_to_play())
+        {
+            self.draw_offer = None;
+        }
+        self.undos
+            .push(self.variant.make_move(&mut self.board, mov));
+        self.history.push(mov.clone());
+        let count = self
+            .previous_positions
+            .entry(self.variant.repetition_key(&self.board))
+            .or_insert(0);
+        *count += 1;
+        if *count >= 5 {
+            self.draw_by_repetition = true;
+        }
+        // an offer can not be accepted once the game is over
+        if self.draw_offer.is_some() && self.status().is_over() {
+            self.draw_offer = None;
+        }
+    }
+
+    // Takes back the last move, None if no move was played yet. A game
+    // ended by resignation or agreement goes on again and a pending draw
+    // offer is withdrawn.
+    pub fn undo(&mut self) -> Option<Move> {
+        let undo = self.undos.pop()?;
+        let mov = self.history.pop()?;
+        self.ended = None;
+        self.draw_offer = None;
+        let key = self.variant.repetition_key(&self.board);
+        if let Some(count) = self.previous_positions.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                self.previous_positions.remove(&key);
+            }
+        }
+        self.variant.unmake_move(&mut self.board, undo);
+        self.draw_by_repetition = self.previous_positions.values().any(|count| *count >= 5);
+        self.redo_stack.push(mov.clone());
+        return Some(mov);
+    }
+
+    // Plays the last move taken back again, None if there is nothing to redo
+    pub fn redo(&mut self) -> Option<Move> {
+        let mov = self.redo_stack.pop()?;
+        self.push_move(&mov);
+        return Some(mov);
+    }
+
+    // The moves played so far, starting from the first position of the game
+    pub fn moves(&self) -> &[Move] {
+        return &self.history;
+    }
+
+    // The position after the given number of half moves, position_at(0) is
+    // the position the game started from
+    pub fn position_at(&self, ply: usize) -> Option<Position> {
+        if ply > self.history.len() {
+            return None;
+        }
+        let mut position = self.start_position.clone();
+        for mov in &self.history[..ply] {
+            self.variant.make_move(&mut position, mov);
+        }
+        return Some(position);
+    }
+
+    // Ends the game with a win for the opponent of the given player
+    pub fn resign(&mut self, color: Color) {
+        if !self.status().is_over() {
+            self.ended = Some(GameStatus::Resignation(color.inverse_color()));
+            self.draw_offer = None;
+        }
+    }
+
+    // The offer stands until the opponent accepts, declines or moves
+    pub fn offer_draw(&mut self, color: Color) {
+        if !self.status().is_over() {
+            self.draw_offer = Some(color);
+        }
+    }
+
+    // Ends the game in a draw if one was offered, false if there was no
+    // offer or the game is already over
+    pub fn accept_draw(&mut self) -> bool {
+        if self.status().is_over() {
+            self.draw_offer = None;
+            return false;
+        }
+        if self.draw_offer.take().is_none() {
+            return false;
+        }
+        self.ended = Some(GameStatus::Agreement);
+        return true;
+    }
+
+    pub fn decline_draw(&mut self) {
+        self.draw_offer = None;
+    }
+
+    pub fn pending_draw_offer(&self) -> Option<Color> {
+        return self.draw_offer.clone();
+    }
+
+    // Sets the value of a PGN tag, replacing an earlier value of the same tag
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
         }
     }
 }
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
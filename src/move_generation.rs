@@ -1,4 +1,7 @@
+use crate::attacks::{bishop_attacks, rook_attacks};
+use crate::bitboard::*;
 pub use crate::move_utils::*;
+use crate::position::{Position, KINGSIDE, QUEENSIDE};
 use crate::square_utils::*;
 use fen::{BoardState, Color, Piece, PieceKind};
 
@@ -12,6 +15,12 @@ impl PieceLocalization for BoardState {
     }
 }
 
+impl PieceLocalization for Position {
+    fn find_piece(&self, piece: Piece) -> Option<usize> {
+        squares(self.pieces(&piece.color, &piece.kind)).next()
+    }
+}
+
 pub trait MoveGeneration {
     // supposed to be public
     fn legal_moves(&self) -> Vec<Move>;
@@ -33,9 +42,6 @@ pub trait MoveGeneration {
         move_dir: i32,
         promotion_rank: usize,
     ) -> Vec<Move>;
-    fn puts_self_in_check(&self, mov: &Move) -> bool;
-    fn print_legal_moves(&self);
-    fn player_in_check(&self) -> bool;
     fn straight_line_moves(
         &self,
         square: usize,
@@ -43,6 +49,9 @@ pub trait MoveGeneration {
         can_capture: bool,
         max_distance: Option<usize>,
     ) -> Vec<Move>;
+    fn puts_self_in_check(&self, mov: &Move) -> bool;
+    fn print_legal_moves(&self);
+    fn player_in_check(&self) -> bool;
 }
 
 pub trait Duplication {
@@ -51,11 +60,120 @@ pub trait Duplication {
 
 impl Duplication for BoardState {
     fn duplicate(&self) -> Self {
-        BoardState::from_fen(&self.to_fen()).unwrap()
+        BoardState {
+            pieces: self.pieces.clone(),
+            side_to_play: self.side_to_play.clone(),
+            white_can_oo: self.white_can_oo,
+            white_can_ooo: self.white_can_ooo,
+            black_can_oo: self.black_can_oo,
+            black_can_ooo: self.black_can_ooo,
+            en_passant_square: self.en_passant_square,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+        }
     }
 }
 
+// All move generation works on the bitboard based Position, a BoardState
+// is converted on every call
 impl MoveGeneration for BoardState {
+    fn print_legal_moves(&self) {
+        Position::from(self).print_legal_moves();
+    }
+    fn player_in_check(&self) -> bool {
+        return Position::from(self).player_in_check();
+    }
+    fn puts_self_in_check(&self, mov: &Move) -> bool {
+        return Position::from(self).puts_self_in_check(mov);
+    }
+    fn legal_moves(&self) -> Vec<Move> {
+        return Position::from(self).legal_moves();
+    }
+    fn generate_pawn_captures(
+        &self,
+        square: usize,
+        move_dir: i32,
+        promotion_rank: usize,
+    ) -> Vec<Move> {
+        return Position::from(self).generate_pawn_captures(square, move_dir, promotion_rank);
+    }
+    fn generate_pawn_moves(&self, square: usize) -> Vec<Move> {
+        return Position::from(self).generate_pawn_moves(square);
+    }
+    fn straight_line_moves(
+        &self,
+        square: usize,
+        move_dirs: Vec<(i32, i32)>,
+        can_capture: bool,
+        max_distance: Option<usize>,
+    ) -> Vec<Move> {
+        return Position::from(self).straight_line_moves(
+            square,
+            move_dirs,
+            can_capture,
+            max_distance,
+        );
+    }
+    fn generate_bishop_moves(&self, square: usize) -> Vec<Move> {
+        return Position::from(self).generate_bishop_moves(square);
+    }
+    fn generate_rook_moves(&self, square: usize) -> Vec<Move> {
+        return Position::from(self).generate_rook_moves(square);
+    }
+    fn generate_queen_moves(&self, square: usize) -> Vec<Move> {
+        return Position::from(self).generate_queen_moves(square);
+    }
+    fn generate_kingside_castles(&self, square: usize) -> Vec<Move> {
+        return Position::from(self).generate_kingside_castles(square);
+    }
+    fn generate_queenside_castles(&self, square: usize) -> Vec<Move> {
+        return Position::from(self).generate_queenside_castles(square);
+    }
+    fn generate_directed_castle(&self, square: usize, direction: i32) -> Vec<Move> {
+        return Position::from(self).generate_directed_castle(square, direction);
+    }
+    fn generate_castling_moves(&self, square: usize) -> Vec<Move> {
+        return Position::from(self).generate_castling_moves(square);
+    }
+    fn generate_king_moves(&self, square: usize, castling: bool) -> Vec<Move> {
+        return Position::from(self).generate_king_moves(square, castling);
+    }
+    fn generate_knight_moves(&self, square: usize) -> Vec<Move> {
+        return Position::from(self).generate_knight_moves(square);
+    }
+    fn generate_moves(&self, castling: bool) -> Vec<Move> {
+        return Position::from(self).generate_moves(castling);
+    }
+}
+
+// one standard move from the square to every square in targets
+fn moves_to(square: usize, targets: Bitboard) -> Vec<Move> {
+    return squares(targets)
+        .map(|target| Move::standard(square, target))
+        .collect();
+}
+
+// pushes the move, or one move per promotion piece if the pawn promotes
+fn push_pawn_move(moves: &mut Vec<Move>, mov: Move, promotion_rank: usize) {
+    if rank(mov.end_square) != promotion_rank {
+        moves.push(mov);
+        return;
+    }
+    let promotion_pieces = [
+        PieceKind::Queen,
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+    ];
+    for promotion_target in promotion_pieces {
+        moves.push(Move {
+            promotion: Some(promotion_target),
+            ..mov.clone()
+        });
+    }
+}
+
+impl MoveGeneration for Position {
     fn print_legal_moves(&self) {
         for mov in self.legal_moves() {
             print!(" {} ", mov);
@@ -63,35 +181,30 @@ impl MoveGeneration for BoardState {
     }
     // checks if the current player can capture opponents king
     fn player_in_check(&self) -> bool {
-        let ref_piece = Piece {
-            kind: PieceKind::King,
-            color: if self.side_to_play == Color::White {
-                Color::Black
-            } else {
-                Color::White
-            },
-        };
-        let king_square = self.find_piece(ref_piece);
-        if !king_square.is_some() {
+        let opponent = self.side_to_play().inverse_color();
+        match self.king_square(&opponent) {
+            Some(king_square) => self.is_square_attacked(king_square, &self.side_to_play()),
             // in real positions this will not happen, it would mean the player has no king
             // but for testing and calculation it can be convenient to consider positions where
             // we can ignore the kings
-            return false;
+            None => false,
         }
-        let king_square = king_square.unwrap();
-        let opponent_moves = self.generate_moves(false);
-        return opponent_moves
-            .iter()
-            .any(|mov| mov.end_square as usize == king_square);
     }
     fn puts_self_in_check(&self, mov: &Move) -> bool {
-        let mut simulation_board = self.duplicate();
-        mov.execute(&mut simulation_board);
+        let mut simulation_board = self.clone();
+        simulation_board.make_move(mov);
         return simulation_board.player_in_check();
     }
     fn legal_moves(&self) -> Vec<Move> {
         let mut moves = self.generate_moves(true);
-        moves.retain(|mv| !self.puts_self_in_check(mv));
+        // every move is tried on the same scratch copy and taken back again
+        let mut simulation_board = self.clone();
+        moves.retain(|mv| {
+            let undo = simulation_board.make_move(mv);
+            let legal = !simulation_board.player_in_check();
+            simulation_board.unmake_move(undo);
+            legal
+        });
         return moves;
     }
 
@@ -102,150 +215,103 @@ impl MoveGeneration for BoardState {
         promotion_rank: usize,
     ) -> Vec<Move> {
         let mut moves = Vec::new();
-        let promotion_pieces = vec![
-            PieceKind::Queen,
-            PieceKind::Knight,
-            PieceKind::Bishop,
-            PieceKind::Rook,
-        ];
-        for capture_dir in vec![1, -1] {
-            let end_file = file(square) as i32 + capture_dir;
-            let end_rank = rank(square) as i32 + move_dir;
-            if !is_on_board(end_rank, end_file) {
-                continue;
-            }
-            let end_square = add_rank(add_file(square, capture_dir) as usize, move_dir) as usize;
-            let mut is_en_passant = false;
-            if self.pieces[end_square].is_none() {
-                if self.en_passant_square.is_none()
-                    || self.en_passant_square.unwrap() != end_square as u8
-                {
-                    continue;
-                }
-                is_en_passant = true;
-            }
-            if rank(end_square) == promotion_rank {
-                for promotion_target in &promotion_pieces {
-                    moves.push(Move {
-                        start_square: square,
-                        end_square,
-                        promotion: Some(promotion_target.clone()),
-                        is_en_passant,
-                        castles: false,
-                    });
-                }
-            } else {
-                moves.push(Move {
-                    start_square: square,
-                    end_square,
-                    promotion: None,
-                    is_en_passant,
-                    castles: false,
-                });
+        let color = if move_dir.is_positive() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let opponent = color.inverse_color();
+        let mut targets = self.occupied_by(&opponent);
+        // only an enemy pawn next to the capturing one can be taken en passant
+        if let Some(en_passant_square) = self.en_passant_square() {
+            let en_passant_square = en_passant_square as usize;
+            let pawn_square = rank(square) * 8 + file(en_passant_square);
+            if !contains(self.occupied(), en_passant_square)
+                && contains(self.pieces(&opponent, &PieceKind::Pawn), pawn_square)
+            {
+                targets |= square_bb(en_passant_square);
             }
         }
+        targets &= PAWN_ATTACKS[color_index(&color)][square];
+        for end_square in squares(targets) {
+            let mov = Move {
+                is_en_passant: !contains(self.occupied(), end_square),
+                ..Move::standard(square, end_square)
+            };
+            push_pawn_move(&mut moves, mov, promotion_rank);
+        }
         return moves;
     }
 
+    // Moves along the given directions up to the first occupied square,
+    // which is included if it holds an enemy piece and captures are allowed
+    fn straight_line_moves(
+        &self,
+        square: usize,
+        move_dirs: Vec<(i32, i32)>,
+        can_capture: bool,
+        max_distance: Option<usize>,
+    ) -> Vec<Move> {
+        let occupancy = self.occupied();
+        let mut targets = sliding_attacks(square, occupancy, &move_dirs)
+            & !self.occupied_by(&self.side_to_play());
+        if !can_capture {
+            targets &= !occupancy;
+        }
+        if let Some(max_distance) = max_distance {
+            targets = squares(targets)
+                .filter(|target| {
+                    file(*target)
+                        .abs_diff(file(square))
+                        .max(rank(*target).abs_diff(rank(square)))
+                        <= max_distance
+                })
+                .fold(EMPTY, |all, target| all | square_bb(target));
+        }
+        return moves_to(square, targets);
+    }
+
     fn generate_pawn_moves(&self, square: usize) -> Vec<Move> {
         let mut moves: Vec<Move> = Vec::new();
-        if !self.pieces[square]
-            .clone()
-            .is_some_and(|pc| pc.color == self.side_to_play)
+        if !self
+            .piece_at(square)
+            .is_some_and(|pc| pc.color == self.side_to_play())
         {
             return moves;
         }
-        let promotion_pieces = vec![
-            PieceKind::Queen,
-            PieceKind::Knight,
-            PieceKind::Bishop,
-            PieceKind::Rook,
-        ];
-        let piece = self.pieces[square].clone().unwrap();
-        let (move_dir, promotion_rank, start_rank): (i32, usize, usize) = match piece.color {
+        let (move_dir, promotion_rank, start_rank): (i32, usize, usize) = match self.side_to_play()
+        {
             Color::White => (1, 7, 1),
             Color::Black => (-1, 0, 6),
         };
         let num_legal_steps = if rank(square) == start_rank { 2 } else { 1 };
         for move_dist in 1..(num_legal_steps + 1) {
-            let end_file = file(square);
             let end_rank = rank(square) as i32 + move_dir * move_dist;
-            if !is_on_board(end_rank, end_file as i32) {
+            if !is_on_board(end_rank, file(square) as i32) {
                 break;
             }
-            let target_square = add_rank(square as usize, move_dir * move_dist) as usize;
-            if self.pieces[target_square].is_some() {
+            let target_square = add_rank(square, move_dir * move_dist) as usize;
+            if contains(self.occupied(), target_square) {
                 break;
             }
-            if rank(target_square) == promotion_rank {
-                for promotion_target in &promotion_pieces {
-                    moves.push(Move {
-                        start_square: square,
-                        end_square: target_square,
-                        promotion: Some(promotion_target.clone()),
-                        is_en_passant: false,
-                        castles: false,
-                    });
-                }
-            } else {
-                moves.push(Move {
-                    start_square: square,
-                    end_square: target_square,
-                    promotion: None,
-                    is_en_passant: false,
-                    castles: false,
-                });
-            }
+            push_pawn_move(
+                &mut moves,
+                Move::standard(square, target_square),
+                promotion_rank,
+            );
         }
         moves.append(&mut self.generate_pawn_captures(square, move_dir, promotion_rank));
         return moves;
     }
 
-    fn straight_line_moves(
-        &self,
-        square: usize,
-        move_dirs: Vec<(i32, i32)>,
-        can_capture: bool,
-        max_distance: Option<usize>,
-    ) -> Vec<Move> {
-        let mut moves = Vec::new();
-        for (file_inc, rank_inc) in move_dirs {
-            let mut distance = 0;
-            loop {
-                distance += 1;
-                let target_file = file(square) as i32 + file_inc * distance;
-                let target_rank = rank(square) as i32 + rank_inc * distance;
-                if !is_on_board(target_rank, target_file)
-                    || max_distance.is_some_and(|d| distance as usize > d)
-                {
-                    break;
-                }
-                let target_square = add_rank(
-                    add_file(square, file_inc * distance) as usize,
-                    rank_inc * distance,
-                ) as usize;
-                if self.pieces[target_square].is_some() {
-                    if can_capture
-                        && self.pieces[target_square].clone().unwrap().color != self.side_to_play
-                    {
-                        moves.push(Move::standard(square, target_square));
-                    }
-                    break;
-                }
-                moves.push(Move::standard(square, target_square));
-            }
-        }
-        return moves;
-    }
-
     fn generate_bishop_moves(&self, square: usize) -> Vec<Move> {
-        let move_dirs = vec![(1, 1), (1, -1), (-1, 1), (-1, -1)];
-        return self.straight_line_moves(square, move_dirs, true, None);
+        let targets = bishop_attacks(square, self.occupied());
+        return moves_to(square, targets & !self.occupied_by(&self.side_to_play()));
     }
 
     fn generate_rook_moves(&self, square: usize) -> Vec<Move> {
-        let move_dirs = vec![(1, 0), (0, -1), (-1, 0), (0, 1)];
-        return self.straight_line_moves(square, move_dirs, true, None);
+        let targets = rook_attacks(square, self.occupied());
+        return moves_to(square, targets & !self.occupied_by(&self.side_to_play()));
     }
 
     fn generate_queen_moves(&self, square: usize) -> Vec<Move> {
@@ -263,40 +329,7 @@ impl MoveGeneration for BoardState {
     }
 
     fn generate_directed_castle(&self, square: usize, direction: i32) -> Vec<Move> {
-        let mut moves = Vec::new();
-        // player can still castle based on previous moves
-        match self.side_to_play {
-            Color::White => {
-                if !self.white_can_ooo {
-                    return moves;
-                }
-            }
-            Color::Black => {
-                if !self.black_can_ooo {
-                    return moves;
-                }
-            }
-        }
-        // cannot castle out of check
-        let mov = Move::standard(square, square);
-        if self.puts_self_in_check(&mov) {
-            return moves;
-        }
-        // some piece stands inbetween king and rook
-        // or one would castle through a check
-        let end_square = (square as i32 + direction) as usize;
-        let mov = Move::standard(square, end_square);
-        if self.pieces[end_square].is_some() || self.puts_self_in_check(&mov) {
-            return moves;
-        }
-        moves.push(Move {
-            start_square: square,
-            end_square: (square as i32 + 2 * direction) as usize,
-            promotion: None,
-            castles: true,
-            is_en_passant: false,
-        });
-        return moves;
+        return self.directed_castle(square, direction, EMPTY);
     }
 
     fn generate_castling_moves(&self, square: usize) -> Vec<Move> {
@@ -307,17 +340,8 @@ impl MoveGeneration for BoardState {
     }
 
     fn generate_king_moves(&self, square: usize, castling: bool) -> Vec<Move> {
-        let move_dirs = vec![
-            (1, 0),
-            (0, -1),
-            (-1, 0),
-            (0, 1),
-            (1, 1),
-            (1, -1),
-            (-1, 1),
-            (-1, -1),
-        ];
-        let mut moves = self.straight_line_moves(square, move_dirs, true, Some(1));
+        let targets = KING_ATTACKS[square] & !self.occupied_by(&self.side_to_play());
+        let mut moves = moves_to(square, targets);
         if castling {
             moves.append(&mut self.generate_castling_moves(square));
         }
@@ -325,57 +349,110 @@ impl MoveGeneration for BoardState {
     }
 
     fn generate_knight_moves(&self, square: usize) -> Vec<Move> {
-        let move_dirs = vec![
-            (2, 1),
-            (2, -1),
-            (-2, 1),
-            (-2, -1),
-            (1, 2),
-            (1, -2),
-            (-1, 2),
-            (-1, -2),
-        ];
+        let targets = KNIGHT_ATTACKS[square] & !self.occupied_by(&self.side_to_play());
+        return moves_to(square, targets);
+    }
+
+    fn generate_moves(&self, castling: bool) -> Vec<Move> {
         let mut moves = Vec::new();
-        for (file_inc, rank_inc) in move_dirs {
-            let target_file = file(square) as i32 + file_inc;
-            let target_rank = rank(square) as i32 + rank_inc;
-            if !is_on_board(target_rank, target_file) {
-                continue;
-            }
-            let target_square = add_rank(add_file(square, file_inc) as usize, rank_inc) as usize;
-            if self.pieces[target_square]
-                .clone()
-                .is_some_and(|p| p.color == self.side_to_play)
-            {
-                continue;
-            }
-            moves.push(Move::standard(square, target_square));
+        let side_to_play = self.side_to_play();
+        for square in squares(self.pieces(&side_to_play, &PieceKind::Pawn)) {
+            moves.append(&mut self.generate_pawn_moves(square));
+        }
+        for square in squares(self.pieces(&side_to_play, &PieceKind::Knight)) {
+            moves.append(&mut self.generate_knight_moves(square));
+        }
+        for square in squares(self.pieces(&side_to_play, &PieceKind::Bishop)) {
+            moves.append(&mut self.generate_bishop_moves(square));
+        }
+        for square in squares(self.pieces(&side_to_play, &PieceKind::Rook)) {
+            moves.append(&mut self.generate_rook_moves(square));
+        }
+        for square in squares(self.pieces(&side_to_play, &PieceKind::Queen)) {
+            moves.append(&mut self.generate_queen_moves(square));
+        }
+        for square in squares(self.pieces(&side_to_play, &PieceKind::King)) {
+            moves.append(&mut self.generate_king_moves(square, castling));
         }
         return moves;
     }
+}
 
-    fn generate_moves(&self, castling: bool) -> Vec<Move> {
+impl Position {
+    // The castling moves of the king on the square, the safe squares are
+    // not checked for attacks when the king passes them
+    pub(crate) fn castling_moves_with_safe_squares(
+        &self,
+        square: usize,
+        safe: Bitboard,
+    ) -> Vec<Move> {
+        let mut moves = self.directed_castle(square, 1, safe);
+        moves.append(&mut self.directed_castle(square, -1, safe));
+        return moves;
+    }
+
+    fn directed_castle(&self, square: usize, direction: i32, safe: Bitboard) -> Vec<Move> {
         let mut moves = Vec::new();
-        for (square, occupant) in self.pieces.iter().enumerate() {
-            if self.pieces[square].is_none()
-                || self.pieces[square].clone().unwrap().color != self.side_to_play
-            {
-                continue;
-            }
-            match occupant {
-                Some(piece) => match piece.kind {
-                    PieceKind::Pawn => moves.append(&mut self.generate_pawn_moves(square)),
-                    PieceKind::Bishop => moves.append(&mut self.generate_bishop_moves(square)),
-                    PieceKind::Rook => moves.append(&mut self.generate_rook_moves(square)),
-                    PieceKind::Queen => moves.append(&mut self.generate_queen_moves(square)),
-                    PieceKind::King => {
-                        moves.append(&mut self.generate_king_moves(square, castling))
-                    }
-                    PieceKind::Knight => moves.append(&mut self.generate_knight_moves(square)),
-                },
-                None => continue,
-            }
+        let side_to_play = self.side_to_play();
+        let opponent = side_to_play.inverse_color();
+        let side = if direction.is_positive() {
+            KINGSIDE
+        } else {
+            QUEENSIDE
+        };
+        // player can still castle based on previous moves
+        let rook_file = match self.castling_rook_file(&side_to_play, side) {
+            Some(rook_file) => rook_file,
+            None => return moves,
+        };
+        // king and rook have to stand on their starting squares, outside
+        // of Chess960 the king starts on the e-file
+        let back_rank = match side_to_play {
+            Color::White => 0,
+            Color::Black => 56,
+        };
+        let rook_square = back_rank + rook_file;
+        let rook = Piece {
+            kind: PieceKind::Rook,
+            color: side_to_play.clone(),
+        };
+        if rank(square) * 8 != back_rank
+            || (!self.is_chess960() && square != back_rank + 4)
+            || self.piece_at(rook_square) != Some(rook)
+        {
+            return moves;
+        }
+        let mut mov = Move {
+            start_square: square,
+            end_square: rook_square,
+            promotion: None,
+            castles: true,
+            is_en_passant: false,
+            drop: None,
+        };
+        let [(_, king_target), (_, rook_target)] = match self.castling_squares(&mov) {
+            Some(squares) => squares,
+            None => return moves,
+        };
+        // some piece other than king and rook stands on a square either of
+        // them moves over or to
+        let lowest = square.min(rook_square).min(king_target).min(rook_target);
+        let highest = square.max(rook_square).max(king_target).max(rook_target);
+        let blocked = (lowest..=highest)
+            .any(|sq| sq != square && sq != rook_square && contains(self.occupied(), sq));
+        if blocked {
+            return moves;
+        }
+        // cannot castle out of, through or into check, unless the square
+        // is one of the safe ones
+        let mut king_path = square.min(king_target)..=square.max(king_target);
+        if king_path.any(|sq| !contains(safe, sq) && self.is_square_attacked(sq, &opponent)) {
+            return moves;
+        }
+        if !self.is_chess960() {
+            mov.end_square = king_target;
         }
+        moves.push(mov);
         return moves;
     }
 }
@@ -1,7 +1,9 @@
+use crate::position::Position;
 use crate::square_utils::*;
-use fen::{BoardState, Color, PieceKind};
+use crate::Error;
+use fen::{BoardState, Color, Piece, PieceKind};
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Eq, Clone)]
 pub struct Move {
     pub start_square: usize,
     pub end_square: usize,
@@ -12,21 +14,28 @@ pub struct Move {
     // in such a case the start_square and end_square are those
     // of the king as to differentiate short and long castles
     pub castles: bool,
+
+    // the piece put on the board from the pocket in Crazyhouse, both the
+    // start_square and end_square are the square it is dropped on
+    pub drop: Option<PieceKind>,
 }
 
 impl std::fmt::Debug for Move {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}{}",
-            square_to_string(self.start_square),
-            square_to_string(self.end_square)
-        )
+        std::fmt::Display::fmt(self, f)
     }
 }
 
 impl std::fmt::Display for Move {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(kind) = &self.drop {
+            return write!(
+                f,
+                "{}@{}",
+                drop_letter(kind),
+                square_to_string(self.end_square)
+            );
+        }
         write!(
             f,
             "{}{}",
@@ -36,6 +45,30 @@ impl std::fmt::Display for Move {
     }
 }
 
+// the letter of a dropped piece, "P@e4" names pawns unlike SAN
+pub fn drop_letter(kind: &PieceKind) -> char {
+    match kind {
+        PieceKind::Pawn => 'P',
+        PieceKind::Knight => 'N',
+        PieceKind::Bishop => 'B',
+        PieceKind::Rook => 'R',
+        PieceKind::Queen => 'Q',
+        PieceKind::King => 'K',
+    }
+}
+
+// the piece kind of a drop letter, kings can not be dropped
+pub fn drop_piece_from_letter(letter: char) -> Option<PieceKind> {
+    match letter {
+        'P' => Some(PieceKind::Pawn),
+        'N' => Some(PieceKind::Knight),
+        'B' => Some(PieceKind::Bishop),
+        'R' => Some(PieceKind::Rook),
+        'Q' => Some(PieceKind::Queen),
+        _ => None,
+    }
+}
+
 pub trait ToggleColor {
     fn inverse_color(&self) -> Self;
 }
@@ -59,43 +92,85 @@ impl Move {
             promotion: None,
             is_en_passant: false,
             castles: false,
+            drop: None,
         };
     }
 
+    // Generate a Crazyhouse drop of a piece from the pocket onto the square
+    pub fn drop_piece(kind: PieceKind, square: usize) -> Self {
+        return Self {
+            start_square: square,
+            end_square: square,
+            promotion: None,
+            is_en_passant: false,
+            castles: false,
+            drop: Some(kind),
+        };
+    }
+
+    // The move given in long algebraic notation like "e2e4", "e7e8q" or
+    // the drop "P@e4", panics if the text is malformed
     pub fn from_long_algebraic(move_str: String, board: &BoardState) -> Move {
-        let start_square = square_from_string(&move_str[0..2]);
-        let end_square = square_from_string(&move_str[2..4]);
-        let is_en_passant = board
-            .en_passant_square
-            .is_some_and(|s| s as usize == end_square);
-        let castles = board.pieces[start_square]
+        return Self::try_from_long_algebraic(&move_str, board)
+            .expect("Chesslib was given a malformed move");
+    }
+
+    // The move given in long algebraic notation, the move is not checked
+    // for legality
+    pub fn try_from_long_algebraic(move_str: &str, board: &BoardState) -> Result<Move, Error> {
+        let malformed = || Error::MalformedMove(move_str.to_string());
+        let chars: Vec<char> = move_str.chars().collect();
+        if chars.len() != 4 && chars.len() != 5 {
+            return Err(malformed());
+        }
+        let square = |chars: &[char]| {
+            try_square_from_string(&chars.iter().collect::<String>()).map_err(|_| malformed())
+        };
+        if chars.len() == 4 && chars[1] == '@' {
+            let kind = drop_piece_from_letter(chars[0]).ok_or_else(malformed)?;
+            return Ok(Self::drop_piece(kind, square(&chars[2..4])?));
+        }
+        let start_square = square(&chars[0..2])?;
+        let end_square = square(&chars[2..4])?;
+        let is_en_passant = board.pieces[start_square]
             .clone()
-            .is_some_and(|p| p.kind == PieceKind::King)
-            && match board.side_to_play {
-                Color::White => start_square == 4 && start_square.abs_diff(end_square) == 2,
-                Color::Black => start_square == 60 && start_square.abs_diff(end_square) == 2,
-            };
-        let promotion = if move_str.len() > 4 {
-            match move_str.chars().collect::<Vec<char>>()[4] {
-                'q' => Some(PieceKind::Queen),
-                'r' => Some(PieceKind::Rook),
-                'n' => Some(PieceKind::Knight),
-                'b' => Some(PieceKind::Bishop),
-                _ => panic!("Chesslib was asked to promote a pawn to an invalid piece"),
-            }
-        } else {
-            None
+            .is_some_and(|p| p.kind == PieceKind::Pawn)
+            && board
+                .en_passant_square
+                .is_some_and(|s| s as usize == end_square);
+        // the king moves two files, or captures its own rook as in Chess960
+        let castles = board.pieces[start_square].clone().is_some_and(|king| {
+            king.kind == PieceKind::King
+                && rank(start_square) == rank(end_square)
+                && (file(start_square).abs_diff(file(end_square)) == 2
+                    || board.pieces[end_square]
+                        == Some(Piece {
+                            kind: PieceKind::Rook,
+                            color: king.color,
+                        }))
+        });
+        let promotion = match chars.get(4) {
+            Some('q') => Some(PieceKind::Queen),
+            Some('r') => Some(PieceKind::Rook),
+            Some('n') => Some(PieceKind::Knight),
+            Some('b') => Some(PieceKind::Bishop),
+            Some(_) => return Err(malformed()),
+            None => None,
         };
-        return Move {
+        return Ok(Move {
             start_square,
             end_square,
             promotion,
             is_en_passant,
             castles,
-        };
+            drop: None,
+        });
     }
 
     pub fn to_long_algebraic(&self) -> String {
+        if self.drop.is_some() {
+            return self.to_string();
+        }
         let mut promotion = "";
         if let Some(promotion_piece) = &self.promotion {
             promotion = match promotion_piece {
@@ -114,73 +189,27 @@ impl Move {
         );
     }
 
+    // Standard algebraic notation of this move, which has to be legal on the board
+    pub fn to_san(&self, board: &BoardState) -> String {
+        return Position::from(board).move_to_san(self);
+    }
+
+    // The legal move on the board described by the standard algebraic notation,
+    // None if there is no such move or the notation is ambiguous
+    pub fn from_san(san: &str, board: &BoardState) -> Option<Move> {
+        return Position::from(board).move_from_san(san);
+    }
+
+    // Like Move::from_san, but tells why no move was found
+    pub fn try_from_san(san: &str, board: &BoardState) -> Result<Move, Error> {
+        return Position::from(board)
+            .move_from_san(san)
+            .ok_or_else(|| Error::IllegalMove(san.to_string()));
+    }
+
     pub fn execute(&self, board: &mut BoardState) {
-        match &board.pieces[self.start_square] {
-            Some(piece) => {
-                let mut end_piece = piece.clone();
-                if let Some(promotion_piece) = &self.promotion {
-                    end_piece.kind = promotion_piece.clone();
-                }
-                if piece.kind == PieceKind::King {
-                    match board.side_to_play {
-                        Color::White => {
-                            board.white_can_oo = false;
-                            board.white_can_ooo = false;
-                        }
-                        Color::Black => {
-                            board.black_can_oo = false;
-                            board.black_can_ooo = false;
-                        }
-                    }
-                }
-                if piece.kind == PieceKind::Rook {
-                    match board.side_to_play {
-                        Color::White => {
-                            if self.start_square == 0 {
-                                board.white_can_ooo = false;
-                            }
-                            if self.start_square == 7 {
-                                board.white_can_oo = false;
-                            }
-                        }
-                        Color::Black => {
-                            if self.start_square == 56 {
-                                board.white_can_ooo = false;
-                            }
-                            if self.start_square == 63 {
-                                board.white_can_oo = false;
-                            }
-                        }
-                    }
-                }
-                board.pieces[self.start_square] = None;
-                board.pieces[self.end_square] = Some(end_piece);
-                if self.is_en_passant {
-                    board.pieces[board.en_passant_square.unwrap() as usize] = None;
-                }
-                if self.castles {
-                    let dir = (self.end_square as i32) - (self.start_square as i32);
-                    if dir.is_positive() {
-                        match board.side_to_play {
-                            Color::White => board.white_can_oo = false,
-                            Color::Black => board.black_can_oo = false,
-                        }
-                        let rook_square = self.start_square + 3;
-                        board.pieces[self.end_square - 1] = board.pieces[rook_square].clone();
-                        board.pieces[rook_square] = None;
-                    } else {
-                        match board.side_to_play {
-                            Color::White => board.white_can_ooo = false,
-                            Color::Black => board.black_can_ooo = false,
-                        }
-                        let rook_square = self.start_square - 4;
-                        board.pieces[self.end_square + 1] = board.pieces[rook_square].clone();
-                        board.pieces[rook_square] = None;
-                    }
-                }
-                board.side_to_play = board.side_to_play.inverse_color();
-            }
-            None => return,
-        }
+        let mut position = Position::from(&*board);
+        position.make_move(self);
+        *board = BoardState::from(&position);
     }
 }
@@ -0,0 +1,70 @@
+use crate::move_generation::MoveGeneration;
+use crate::move_utils::Move;
+use crate::position::Position;
+use crate::variant::Variant;
+
+impl Position {
+    // Number of leaf nodes of the legal move tree of the given depth,
+    // the standard way of validating a move generator
+    pub fn perft(&self, depth: usize) -> u64 {
+        let mut position = self.clone();
+        return perft_in_place(&mut position, depth);
+    }
+
+    // The perft of each legal move, with depth - 1 left after the move,
+    // sorted by the long algebraic notation of the moves to line them up
+    // with the divide output of other engines
+    pub fn divide(&self, depth: usize) -> Vec<(Move, u64)> {
+        let mut position = self.clone();
+        let mut nodes = Vec::new();
+        if depth == 0 {
+            return nodes;
+        }
+        for mov in self.legal_moves() {
+            let undo = position.make_move(&mov);
+            nodes.push((mov, perft_in_place(&mut position, depth - 1)));
+            position.unmake_move(undo);
+        }
+        nodes.sort_by_key(|(mov, _)| mov.to_long_algebraic());
+        return nodes;
+    }
+}
+
+fn perft_in_place(position: &mut Position, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = position.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for mov in moves {
+        let undo = position.make_move(&mov);
+        nodes += perft_in_place(position, depth - 1);
+        position.unmake_move(undo);
+    }
+    return nodes;
+}
+
+// perft_in_place by the rules of the variant, see Variant::perft
+pub(crate) fn variant_perft<V: Variant + ?Sized>(
+    variant: &V,
+    position: &mut Position,
+    depth: usize,
+) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = variant.legal_moves(position);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for mov in moves {
+        let undo = variant.make_move(position, &mov);
+        nodes += variant_perft(variant, position, depth - 1);
+        variant.unmake_move(position, undo);
+    }
+    return nodes;
+}
@@ -0,0 +1,467 @@
+use crate::move_utils::Move;
+use crate::position::Position;
+use crate::variant::{self, Standard};
+use crate::Game;
+use std::io::BufRead;
+use std::sync::Arc;
+
+// Reading and writing games in Portable Game Notation
+
+// the tags every exported game starts with, in this order
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+const MAX_LINE_LENGTH: usize = 80;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    // "*", the game is still going on or the result is not known
+    Unknown,
+}
+
+impl GameResult {
+    pub fn from_pgn(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(Self::WhiteWins),
+            "0-1" => Some(Self::BlackWins),
+            "1/2-1/2" => Some(Self::Draw),
+            "*" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+            Self::Unknown => "*",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(std::io::Error),
+    // a tag pair that is not of the form [Name "value"]
+    InvalidTag(String),
+    InvalidFen(String),
+    // a move that is not legal in the position it is played in
+    IllegalMove { ply: usize, san: String },
+    UnexpectedCharacter(char),
+    UnterminatedComment,
+    UnbalancedVariation,
+    // a Variant tag naming rules that are not implemented
+    UnsupportedVariant(String),
+}
+
+impl std::fmt::Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read pgn: {}", err),
+            Self::InvalidTag(tag) => write!(f, "invalid tag pair: {}", tag),
+            Self::InvalidFen(fen) => write!(f, "invalid FEN tag: {}", fen),
+            Self::IllegalMove { ply, san } => write!(f, "illegal move {} at ply {}", san, ply),
+            Self::UnexpectedCharacter(c) => write!(f, "unexpected character '{}'", c),
+            Self::UnterminatedComment => write!(f, "comment is never closed"),
+            Self::UnbalancedVariation => write!(f, "unbalanced parentheses in variation"),
+            Self::UnsupportedVariant(name) => write!(f, "unsupported variant {}", name),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<std::io::Error> for PgnError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+// A game read from PGN. The game holds the position after the last move
+// of the main line, variations are skipped.
+#[derive(Clone)]
+pub struct PgnGame {
+    // all tag pairs in the order they appear in
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<Move>,
+    pub result: GameResult,
+    pub game: Game,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        return self
+            .tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str());
+    }
+}
+
+// Parses the first game in the text
+pub fn read_game(pgn: &str) -> Result<PgnGame, PgnError> {
+    let text = without_escaped_lines(pgn);
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+    };
+    return parser.game();
+}
+
+// Lines with a % in the first column are escaped and not part of the PGN
+fn without_escaped_lines(pgn: &str) -> String {
+    return pgn.lines().filter(|line| !line.starts_with('%')).fold(
+        String::new(),
+        |mut text, line| {
+            text.push_str(line);
+            text.push('\n');
+            text
+        },
+    );
+}
+
+// Reads one game after the other from a PGN file, only the text of the
+// game currently being parsed is kept in memory
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    // first line of the next game, read while looking for the end of the last one
+    pending: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: None,
+        }
+    }
+
+    // the text of the next game, None at the end of the input
+    fn next_game_text(&mut self) -> Result<Option<String>, PgnError> {
+        let mut text = self.pending.take().unwrap_or_default();
+        let mut seen_movetext = false;
+        let mut in_comment = false;
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let trimmed = line.trim_start();
+            if !in_comment && trimmed.starts_with('[') && seen_movetext {
+                self.pending = Some(line);
+                break;
+            }
+            // lines starting with % are escaped and ignored
+            if line.starts_with('%') {
+                continue;
+            }
+            // braces in tag values do not open comments
+            let is_tag = !in_comment && trimmed.starts_with('[');
+            if !in_comment && !trimmed.is_empty() && !is_tag {
+                seen_movetext = true;
+            }
+            if is_tag {
+                text.push_str(&line);
+                continue;
+            }
+            for c in line.chars() {
+                match c {
+                    '{' => in_comment = true,
+                    '}' => in_comment = false,
+                    ';' if !in_comment => break,
+                    _ => (),
+                }
+            }
+            text.push_str(&line);
+        }
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some(text));
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return match self.next_game_text() {
+            Ok(Some(text)) => Some(read_game(&text)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        };
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn game(&mut self) -> Result<PgnGame, PgnError> {
+        let mut tags = Vec::new();
+        self.skip_whitespace();
+        while self.chars.peek() == Some(&'[') {
+            tags.push(self.tag()?);
+            self.skip_whitespace();
+        }
+
+        let variant_name = tags
+            .iter()
+            .find(|(name, _)| name == "Variant")
+            .map(|(_, value)| value.as_str());
+        let variant = match variant_name {
+            Some(name) => variant::from_name(name)
+                .ok_or_else(|| PgnError::UnsupportedVariant(name.to_string()))?,
+            None => Arc::new(Standard),
+        };
+        let chess960 = variant_name.is_some_and(variant::is_chess960_name);
+        let board = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) if chess960 => {
+                Position::from_chess960_fen(fen).map_err(|_| PgnError::InvalidFen(fen.clone()))?
+            }
+            Some((_, fen)) => variant
+                .position_from_fen(fen)
+                .map_err(|_| PgnError::InvalidFen(fen.clone()))?,
+            None => variant.starting_position(),
+        };
+        let mut game = Game::variant_from_position(variant, board);
+        game.tags = tags.clone();
+        let mut moves = Vec::new();
+        let mut result = None;
+        let mut variation_depth = 0;
+        loop {
+            self.skip_whitespace();
+            let c = match self.chars.peek() {
+                Some(c) => *c,
+                None => break,
+            };
+            match c {
+                '{' => {
+                    self.chars.next();
+                    if !self.chars.any(|c| c == '}') {
+                        return Err(PgnError::UnterminatedComment);
+                    }
+                }
+                ';' => {
+                    self.chars.any(|c| c == '\n');
+                }
+                '(' => {
+                    self.chars.next();
+                    variation_depth += 1;
+                }
+                ')' => {
+                    self.chars.next();
+                    if variation_depth == 0 {
+                        return Err(PgnError::UnbalancedVariation);
+                    }
+                    variation_depth -= 1;
+                }
+                // numeric annotation glyph, e.g. $1
+                '$' => {
+                    self.chars.next();
+                    self.take_while(|c| c.is_ascii_digit());
+                }
+                '.' => {
+                    self.chars.next();
+                }
+                // the next game starts, the result token was left out
+                '[' if variation_depth == 0 => break,
+                '*' => {
+                    self.chars.next();
+                    if variation_depth == 0 {
+                        result = Some(GameResult::Unknown);
+                        break;
+                    }
+                }
+                // drops in Crazyhouse are written "N@f3" or "@e4"
+                c if c.is_ascii_alphanumeric() || c == '@' => {
+                    let symbol =
+                        self.take_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/!?@".contains(c));
+                    if variation_depth > 0 || symbol.chars().all(|c| c.is_ascii_digit()) {
+                        continue;
+                    }
+                    if let Some(token) = GameResult::from_pgn(&symbol) {
+                        result = Some(token);
+                        break;
+                    }
+                    let mov = game
+                        .variant()
+                        .move_from_san(&game.board, &symbol)
+                        .ok_or_else(|| PgnError::IllegalMove {
+                            ply: moves.len() + 1,
+                            san: symbol.clone(),
+                        })?;
+                    game.execute_move(&mov);
+                    moves.push(mov);
+                }
+                c => return Err(PgnError::UnexpectedCharacter(c)),
+            }
+        }
+        if variation_depth != 0 {
+            return Err(PgnError::UnbalancedVariation);
+        }
+
+        let result = result
+            .or_else(|| {
+                tags.iter()
+                    .find(|(name, _)| name == "Result")
+                    .and_then(|(_, value)| GameResult::from_pgn(value))
+            })
+            .unwrap_or(GameResult::Unknown);
+        return Ok(PgnGame {
+            tags,
+            moves,
+            result,
+            game,
+        });
+    }
+
+    // [Name "value"], quotes and backslashes in the value are escaped by a backslash
+    fn tag(&mut self) -> Result<(String, String), PgnError> {
+        self.chars.next();
+        self.skip_whitespace();
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        self.skip_whitespace();
+        if name.is_empty() || self.chars.next() != Some('"') {
+            return Err(PgnError::InvalidTag(name));
+        }
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => break,
+                Some('\\') => value.extend(self.chars.next()),
+                Some(c) => value.push(c),
+                None => return Err(PgnError::InvalidTag(name)),
+            }
+        }
+        self.skip_whitespace();
+        if self.chars.next() != Some(']') {
+            return Err(PgnError::InvalidTag(name));
+        }
+        return Ok((name, value));
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.chars.next_if(|c| predicate(*c)) {
+            taken.push(c);
+        }
+        return taken;
+    }
+}
+
+impl Game {
+    // The result as far as it follows from the game status
+    pub fn result(&self) -> GameResult {
+        return self.status().result();
+    }
+
+    // The game in PGN export format: the seven tag roster, the Termination
+    // tag of a finished game, the other tags, a SetUp and FEN tag if the
+    // game did not start from the standard position or is a Chess960 game,
+    // and the SAN movetext wrapped at 80 columns. A game that is not over on
+    // the board keeps the result of its Result tag, e.g. after a resignation.
+    pub fn to_pgn(&self) -> String {
+        let status = self.status();
+        let result = match status.result() {
+            GameResult::Unknown => self
+                .tag("Result")
+                .and_then(GameResult::from_pgn)
+                .unwrap_or(GameResult::Unknown),
+            result => result,
+        };
+        let mut pgn = String::new();
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => result.to_pgn(),
+                _ => self.tag(name).unwrap_or(default),
+            };
+            pgn.push_str(&format_tag(name, value));
+        }
+        if status.is_over() {
+            pgn.push_str(&format_tag("Termination", status.termination()));
+        }
+        let chess960 = self.start_position.is_chess960();
+        let variant_name = match self.variant().name() {
+            "Standard" if chess960 => Some("Chess960"),
+            "Standard" => None,
+            name => Some(name),
+        };
+        if let Some(name) = variant_name {
+            pgn.push_str(&format_tag("Variant", name));
+        }
+        let start_fen = self.start_position.to_fen();
+        if start_fen != self.variant().starting_position().to_fen() || chess960 {
+            pgn.push_str(&format_tag("SetUp", "1"));
+            pgn.push_str(&format_tag("FEN", &start_fen));
+        }
+        for (name, value) in &self.tags {
+            let written = SEVEN_TAG_ROSTER.iter().any(|(tag, _)| tag == name)
+                || (name == "Termination" && status.is_over())
+                || (name == "Variant" && variant_name.is_some())
+                || name == "SetUp"
+                || name == "FEN";
+            if !written {
+                pgn.push_str(&format_tag(name, value));
+            }
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        let mut position = self.start_position.clone();
+        for (ply, mov) in self.history.iter().enumerate() {
+            let move_number = position.fullmove_number();
+            match position.side_to_play() {
+                fen::Color::White => tokens.push(format!("{}.", move_number)),
+                fen::Color::Black if ply == 0 => tokens.push(format!("{}...", move_number)),
+                fen::Color::Black => (),
+            }
+            tokens.push(self.variant().move_to_san(&position, mov));
+            self.variant().make_move(&mut position, mov);
+        }
+        tokens.push(result.to_pgn().to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        return pgn;
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        return self
+            .tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str());
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    return format!("[{} \"{}\"]\n", name, value);
+}
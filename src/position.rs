@@ -0,0 +1,863 @@
+use crate::attacks::{bishop_attacks, rook_attacks};
+use crate::bitboard::*;
+use crate::move_generation::MoveGeneration;
+use crate::move_utils::{drop_letter, drop_piece_from_letter, Move, ToggleColor};
+use crate::square_utils::{file, rank};
+use crate::zobrist::*;
+use crate::Error;
+use fen::{BoardState, Color, FenResult, Piece, PieceKind};
+
+pub const KINGSIDE: usize = 0;
+pub const QUEENSIDE: usize = 1;
+
+// the piece kinds a Crazyhouse pocket holds, in the order of kind_index
+pub(crate) const POCKET_KINDS: [PieceKind; 5] = [
+    PieceKind::Pawn,
+    PieceKind::Knight,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::Queen,
+];
+
+// Everything needed to take back a move made with Position::make_move
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Undo {
+    mov: Move,
+    // None if there was no piece on the start square and nothing changed
+    moved: Option<Piece>,
+    captured: Option<Piece>,
+    en_passant_capture: Option<(usize, Piece)>,
+    // the squares the king and the rook were moved from and to when castling
+    castled: Option<[(usize, usize); 2]>,
+    // the pieces blown up by a capture in atomic chess, the capturing piece
+    // included, kept by variant::Atomic
+    pub(crate) exploded: Vec<(usize, Piece)>,
+    castling_rooks: [[Option<u8>; 2]; 2],
+    // the pockets and promoted pieces before the move, kept by Crazyhouse
+    pub(crate) pocket_state: Option<PocketState>,
+    en_passant_square: Option<u8>,
+    halfmove_clock: u64,
+    fullmove_number: u64,
+    hash: u64,
+}
+
+// The pockets and the promoted pieces of a Crazyhouse position
+pub(crate) type PocketState = ([[u8; 5]; 2], Bitboard);
+
+impl Undo {
+    // the piece taken by the move and the square it stood on
+    pub(crate) fn captured(&self) -> Option<(usize, Piece)> {
+        let captured = self
+            .captured
+            .clone()
+            .map(|piece| (self.mov.end_square, piece));
+        return captured.or(self.en_passant_capture.clone());
+    }
+}
+
+// Board representation used for move generation. Every piece kind of every
+// color is kept in its own bitboard so that generating moves or looking for
+// attackers never has to scan all 64 squares.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    pieces: [[Bitboard; 6]; 2],
+    occupancy: [Bitboard; 2],
+    side_to_play: Color,
+    // the files of the rooks that can still castle, indexed by color first,
+    // then by KINGSIDE or QUEENSIDE
+    castling_rooks: [[Option<u8>; 2]; 2],
+    // castling moves are written as the king capturing its own rook and
+    // the FEN names the rook files if they are not the outermost rooks
+    chess960: bool,
+    // the FEN lists the pockets and marks promoted pieces, the rules of
+    // Crazyhouse are in variant::Crazyhouse
+    crazyhouse: bool,
+    // the number of pieces in the pockets, indexed by color first, then by
+    // the kind index of everything but the king
+    pockets: [[u8; 5]; 2],
+    // pieces that were pawns before they promoted, they are pocketed as pawns
+    promoted: Bitboard,
+    en_passant_square: Option<u8>,
+    halfmove_clock: u64,
+    fullmove_number: u64,
+    // Zobrist hash of everything but the en passant square, see Position::hash
+    hash: u64,
+}
+
+impl Position {
+    // Reads standard FEN as well as the Shredder-FEN and X-FEN castling
+    // fields of Chess960, which name the files of the castling rooks. The
+    // position is a Chess960 one only if the castling field names files,
+    // KQkq rights need the king and rook on their standard squares and are
+    // dropped otherwise.
+    pub fn from_fen(fen: &str) -> FenResult<'_, Self> {
+        return Ok(Self::read_fen(fen, false)?.0);
+    }
+
+    // Like Position::from_fen, but rejects piece placements the fen crate
+    // would quietly repair: every rank has to add up to eight squares and
+    // each side needs exactly one king. The other fields are checked before
+    // the fen crate sees them, castling rights that do not match the
+    // position and impossible en passant squares are rejected as well.
+    pub fn try_from_fen(fen: &str) -> Result<Self, Error> {
+        check_fields(fen)?;
+        return match Self::read_fen(fen, false) {
+            Ok((position, true)) if position.en_passant_is_possible() => Ok(position),
+            _ => Err(Error::InvalidFen(fen.to_string())),
+        };
+    }
+
+    // The en passant square is empty, on the rank the pawn of the player
+    // not on the move skipped, and that pawn stands right in front of it
+    fn en_passant_is_possible(&self) -> bool {
+        let square = match self.en_passant_square {
+            Some(square) => square as usize,
+            None => return true,
+        };
+        let opponent = self.side_to_play.inverse_color();
+        let (skipped_rank, pawn_square) = match self.side_to_play {
+            Color::White => (5, square.wrapping_sub(8)),
+            Color::Black => (2, square + 8),
+        };
+        return rank(square) == skipped_rank
+            && !contains(self.occupied(), square)
+            && contains(self.pieces(&opponent, &PieceKind::Pawn), pawn_square);
+    }
+
+    // Like Position::from_fen, but always a Chess960 position, which the
+    // FEN can not tell when the castling field is written as KQkq
+    pub fn from_chess960_fen(fen: &str) -> FenResult<'_, Self> {
+        return Ok(Self::read_fen(fen, true)?.0);
+    }
+
+    // the position and whether every castling right in the FEN matched it
+    fn read_fen(fen: &str, chess960: bool) -> FenResult<'_, (Self, bool)> {
+        let mut position = Self::from(&BoardState::from_fen(fen)?);
+        let castling = fen.split(' ').nth(2).unwrap_or("-");
+        position.set_castling_rooks(castling);
+        position.chess960 = chess960
+            || castling
+                .chars()
+                .any(|c| c.is_ascii_alphabetic() && !"KQkq".contains(c));
+        let named = castling.chars().filter(char::is_ascii_alphabetic).count();
+        let read = position.castling_rooks.iter().flatten().flatten().count();
+        let matched = position.remove_unmatched_castling_rights();
+        return Ok((position, matched && read == named));
+    }
+
+    // Chess960 positions write their castling rights in X-FEN, that is KQkq
+    // for the outermost rooks and the file of the rook otherwise
+    pub fn to_fen(&self) -> String {
+        let mut fen = BoardState::from(self).to_fen();
+        if self.crazyhouse {
+            fen = self.crazyhouse_fen(&fen);
+        }
+        if !self.chess960 {
+            return fen;
+        }
+        let mut parts: Vec<String> = fen.split(' ').map(str::to_string).collect();
+        parts[2] = self.x_fen_castling();
+        return parts.join(" ");
+    }
+
+    pub fn is_chess960(&self) -> bool {
+        return self.chess960;
+    }
+
+    // Reads the FEN of a Crazyhouse position, which lists the pockets in
+    // brackets after the pieces, e.g. "[QNpp]", and marks promoted pieces
+    // with a "~" like "Q~". Plain FEN reads as a position with empty pockets.
+    pub fn from_crazyhouse_fen(fen: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidFen(fen.to_string());
+        let (placement, rest) = fen.trim().split_once(' ').ok_or_else(invalid)?;
+        let (placement, pocket) = match placement.split_once('[') {
+            Some((placement, pocket)) => (placement, pocket.strip_suffix(']').ok_or_else(invalid)?),
+            None => (placement, ""),
+        };
+        // squares are listed from a8 to h8, then a7 and so on
+        let mut promoted = EMPTY;
+        let mut square: usize = 56;
+        for c in placement.chars() {
+            match c {
+                '/' => square = square.checked_sub(16).ok_or_else(invalid)?,
+                '1'..='8' => square += c as usize - '0' as usize,
+                '~' if (1..=64).contains(&square) => promoted |= square_bb(square - 1),
+                _ => square += 1,
+            }
+        }
+        // only a piece other than a pawn or king can be marked as promoted
+        let mut previous = None;
+        for c in placement.chars() {
+            if c == '~' && !previous.is_some_and(|p: char| "NBRQnbrq".contains(p)) {
+                return Err(invalid());
+            }
+            previous = Some(c);
+        }
+        let standard_fen = format!("{} {}", placement.replace('~', ""), rest);
+        let mut position = Self::try_from_fen(&standard_fen).map_err(|_| invalid())?;
+        position.crazyhouse = true;
+        position.set_promoted(promoted & position.occupied());
+        for letter in pocket.chars() {
+            let color = if letter.is_ascii_uppercase() { 0 } else { 1 };
+            let kind = drop_piece_from_letter(letter.to_ascii_uppercase())
+                .map(|kind| kind_index(&kind))
+                .ok_or_else(invalid)?;
+            let count = position.pockets[color][kind] + 1;
+            if count > 16 {
+                return Err(invalid());
+            }
+            position.set_pocket(color, kind, count);
+        }
+        return Ok(position);
+    }
+
+    pub fn is_crazyhouse(&self) -> bool {
+        return self.crazyhouse;
+    }
+
+    // how many pieces of the kind the player can drop
+    pub fn pocket(&self, color: &Color, kind: &PieceKind) -> u8 {
+        return match POCKET_KINDS
+            .iter()
+            .position(|pocket_kind| pocket_kind == kind)
+        {
+            Some(kind) => self.pockets[color_index(color)][kind],
+            None => 0,
+        };
+    }
+
+    // checks if the piece on the square was a pawn before it promoted
+    pub fn is_promoted(&self, square: usize) -> bool {
+        return contains(self.promoted, square);
+    }
+
+    pub(crate) fn pocket_state(&self) -> PocketState {
+        return (self.pockets, self.promoted);
+    }
+
+    // puts back the pockets and promoted pieces of an earlier position, the
+    // hash has to be restored by the caller
+    pub(crate) fn restore_pocket_state(&mut self, (pockets, promoted): PocketState) {
+        self.pockets = pockets;
+        self.promoted = promoted;
+    }
+
+    pub(crate) fn set_promoted(&mut self, promoted: Bitboard) {
+        for square in squares(self.promoted ^ promoted) {
+            self.hash ^= PROMOTED_KEYS[square];
+        }
+        self.promoted = promoted;
+    }
+
+    pub(crate) fn set_pocket(&mut self, color: usize, kind: usize, count: u8) {
+        let keys = POCKET_KEYS[color][kind];
+        let old_count = self.pockets[color][kind] as usize;
+        if old_count > 0 {
+            self.hash ^= keys[old_count];
+        }
+        if count > 0 {
+            self.hash ^= keys[count as usize];
+        }
+        self.pockets[color][kind] = count;
+    }
+
+    // the pockets and promoted pieces of Crazyhouse FEN, see from_crazyhouse_fen
+    fn crazyhouse_fen(&self, fen: &str) -> String {
+        let (placement, rest) = fen.split_once(' ').unwrap_or((fen, ""));
+        let mut marked = String::new();
+        let mut square = 56;
+        for c in placement.chars() {
+            marked.push(c);
+            match c {
+                '/' => square -= 16,
+                '1'..='8' => square += c as usize - '0' as usize,
+                _ => {
+                    if self.is_promoted(square) {
+                        marked.push('~');
+                    }
+                    square += 1;
+                }
+            }
+        }
+        let mut pocket = String::new();
+        for color in 0..2 {
+            for kind in (0..5).rev() {
+                let letter = drop_letter(&POCKET_KINDS[kind]);
+                for _ in 0..self.pockets[color][kind] {
+                    pocket.push(match color {
+                        0 => letter,
+                        _ => letter.to_ascii_lowercase(),
+                    });
+                }
+            }
+        }
+        return format!("{}[{}] {}", marked, pocket, rest);
+    }
+
+    // replaces the rights read by BoardState, which only knows KQkq
+    fn set_castling_rooks(&mut self, castling: &str) {
+        let mut rooks = [[None; 2]; 2];
+        for c in castling.chars() {
+            let color = if c.is_ascii_uppercase() { 0 } else { 1 };
+            let king_file = match self.king_square(&color_from_index(color)) {
+                Some(square) if rank(square) == [0, 7][color] => file(square),
+                _ => continue,
+            };
+            let back_rank = [0, 56][color];
+            let own_rooks: Vec<usize> = (0..8)
+                .filter(|rook_file| {
+                    contains(
+                        self.pieces[color][kind_index(&PieceKind::Rook)],
+                        back_rank + rook_file,
+                    )
+                })
+                .collect();
+            let (side, rook_file) = match c.to_ascii_lowercase() {
+                // the outermost rook on that side of the king
+                'k' => (
+                    KINGSIDE,
+                    own_rooks
+                        .iter()
+                        .rev()
+                        .find(|rook| **rook > king_file)
+                        .copied()
+                        .unwrap_or(7),
+                ),
+                'q' => (
+                    QUEENSIDE,
+                    own_rooks
+                        .iter()
+                        .find(|rook| **rook < king_file)
+                        .copied()
+                        .unwrap_or(0),
+                ),
+                file @ 'a'..='h' => {
+                    let rook_file = file as usize - 'a' as usize;
+                    if rook_file > king_file {
+                        (KINGSIDE, rook_file)
+                    } else if rook_file < king_file {
+                        (QUEENSIDE, rook_file)
+                    } else {
+                        continue;
+                    }
+                }
+                _ => continue,
+            };
+            rooks[color][side] = Some(rook_file as u8);
+        }
+        for color in 0..2 {
+            for side in [KINGSIDE, QUEENSIDE] {
+                if rooks[color][side].is_some() != self.castling_rooks[color][side].is_some() {
+                    self.hash ^= CASTLING_KEYS[color][side];
+                }
+            }
+        }
+        self.castling_rooks = rooks;
+    }
+
+    // Drops the castling rights without a rook on their square and, outside
+    // of Chess960, those whose king or rook is not on its standard square.
+    // False if a right was dropped.
+    fn remove_unmatched_castling_rights(&mut self) -> bool {
+        let mut matched = true;
+        for color in 0..2 {
+            let back_rank = [0, 56][color];
+            let king_file = self.king_square(&color_from_index(color)).map(file);
+            for side in [KINGSIDE, QUEENSIDE] {
+                let rook_file = match self.castling_rooks[color][side] {
+                    Some(rook_file) => rook_file as usize,
+                    None => continue,
+                };
+                let corner = if side == KINGSIDE { 7 } else { 0 };
+                let has_rook = contains(
+                    self.pieces[color][kind_index(&PieceKind::Rook)],
+                    back_rank + rook_file,
+                );
+                if !has_rook || !(self.chess960 || (king_file == Some(4) && rook_file == corner)) {
+                    self.remove_castling_right(color, side);
+                    matched = false;
+                }
+            }
+        }
+        return matched;
+    }
+
+    fn x_fen_castling(&self) -> String {
+        let mut castling = String::new();
+        for color in 0..2 {
+            let back_rank = [0, 56][color];
+            let rooks = self.pieces[color][kind_index(&PieceKind::Rook)];
+            for side in [KINGSIDE, QUEENSIDE] {
+                let rook_file = match self.castling_rooks[color][side] {
+                    Some(rook_file) => rook_file as usize,
+                    None => continue,
+                };
+                // another rook further out would be the one KQkq refers to
+                let outer_files = match side {
+                    KINGSIDE => rook_file + 1..8,
+                    _ => 0..rook_file,
+                };
+                let letter = if outer_files
+                    .into_iter()
+                    .any(|outer| contains(rooks, back_rank + outer))
+                {
+                    (b'a' + rook_file as u8) as char
+                } else {
+                    ['k', 'q'][side]
+                };
+                castling.push(match color {
+                    0 => letter.to_ascii_uppercase(),
+                    _ => letter,
+                });
+            }
+        }
+        if castling.is_empty() {
+            return "-".to_string();
+        }
+        return castling;
+    }
+
+    pub fn side_to_play(&self) -> Color {
+        return self.side_to_play.clone();
+    }
+
+    pub fn can_castle(&self, color: &Color, side: usize) -> bool {
+        return self.castling_rooks[color_index(color)][side].is_some();
+    }
+
+    // the file of the rook the player can castle with on the given side
+    pub fn castling_rook_file(&self, color: &Color, side: usize) -> Option<usize> {
+        return self.castling_rooks[color_index(color)][side].map(|file| file as usize);
+    }
+
+    pub fn en_passant_square(&self) -> Option<u8> {
+        return self.en_passant_square;
+    }
+
+    pub fn halfmove_clock(&self) -> u64 {
+        return self.halfmove_clock;
+    }
+
+    pub fn fullmove_number(&self) -> u64 {
+        return self.fullmove_number;
+    }
+
+    // 64 bit Zobrist hash of the pieces, side to play, castling rights, en
+    // passant file and the Crazyhouse pockets and promoted pieces. The en
+    // passant file only counts if a pawn of the side to play stands next to
+    // the pawn that just made a double step.
+    pub fn hash(&self) -> u64 {
+        let en_passant_square = match self.en_passant_square {
+            Some(square) => square as usize,
+            None => return self.hash,
+        };
+        let opponent = self.side_to_play.inverse_color();
+        let capturing_pawns = PAWN_ATTACKS[color_index(&opponent)][en_passant_square]
+            & self.pieces(&self.side_to_play, &PieceKind::Pawn);
+        if capturing_pawns == EMPTY {
+            return self.hash;
+        }
+        return self.hash ^ EN_PASSANT_KEYS[en_passant_square % 8];
+    }
+
+    // The hash identifying the position for the repetition rules. Unlike
+    // Position::hash the en passant file only counts if the capture is
+    // legal, a pinned pawn does not make the position a different one.
+    pub fn repetition_key(&self) -> u64 {
+        return self.repetition_key_by(|| self.legal_moves());
+    }
+
+    // Position::repetition_key with the legal moves of a variant, they are
+    // only generated if a pawn could capture en passant
+    pub(crate) fn repetition_key_by(&self, legal_moves: impl FnOnce() -> Vec<Move>) -> u64 {
+        let hash = self.hash();
+        if hash == self.hash || legal_moves().iter().any(|mov| mov.is_en_passant) {
+            return hash;
+        }
+        return self.hash;
+    }
+
+    // all pieces of the given kind and color
+    pub fn pieces(&self, color: &Color, kind: &PieceKind) -> Bitboard {
+        return self.pieces[color_index(color)][kind_index(kind)];
+    }
+
+    // all squares occupied by a piece of the given color
+    pub fn occupied_by(&self, color: &Color) -> Bitboard {
+        return self.occupancy[color_index(color)];
+    }
+
+    // all squares occupied by any piece
+    pub fn occupied(&self) -> Bitboard {
+        return self.occupancy[0] | self.occupancy[1];
+    }
+
+    pub fn piece_at(&self, square: usize) -> Option<Piece> {
+        if !contains(self.occupied(), square) {
+            return None;
+        }
+        let color = if contains(self.occupancy[0], square) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let kind = self.pieces[color_index(&color)]
+            .iter()
+            .position(|bitboard| contains(*bitboard, square))?;
+        return Some(Piece {
+            kind: kind_from_index(kind),
+            color,
+        });
+    }
+
+    pub fn king_square(&self, color: &Color) -> Option<usize> {
+        return squares(self.pieces(color, &PieceKind::King)).next();
+    }
+
+    pub(crate) fn put_piece(&mut self, square: usize, piece: &Piece) {
+        let color = color_index(&piece.color);
+        self.pieces[color][kind_index(&piece.kind)] |= square_bb(square);
+        self.occupancy[color] |= square_bb(square);
+        self.hash ^= PIECE_KEYS[color][kind_index(&piece.kind)][square];
+    }
+
+    pub(crate) fn remove_piece(&mut self, square: usize) -> Option<Piece> {
+        let piece = self.piece_at(square)?;
+        let color = color_index(&piece.color);
+        self.pieces[color][kind_index(&piece.kind)] &= !square_bb(square);
+        self.occupancy[color] &= !square_bb(square);
+        self.hash ^= PIECE_KEYS[color][kind_index(&piece.kind)][square];
+        return Some(piece);
+    }
+
+    pub(crate) fn remove_castling_right(&mut self, color: usize, side: usize) {
+        if self.castling_rooks[color][side].is_some() {
+            self.castling_rooks[color][side] = None;
+            self.hash ^= CASTLING_KEYS[color][side];
+        }
+    }
+
+    // the color and side of the castling right tied to a rook on the square
+    pub(crate) fn castling_rook_at(&self, square: usize) -> Option<(usize, usize)> {
+        for color in 0..2 {
+            if rank(square) != [0, 7][color] {
+                continue;
+            }
+            for side in [KINGSIDE, QUEENSIDE] {
+                if self.castling_rooks[color][side] == Some(file(square) as u8) {
+                    return Some((color, side));
+                }
+            }
+        }
+        return None;
+    }
+
+    // The squares the king and the rook move from and to when castling.
+    // Castling moves end either on the king's target square or, as in
+    // Chess960, on the square of the rook.
+    pub fn castling_squares(&self, mov: &Move) -> Option<[(usize, usize); 2]> {
+        let color = color_index(&self.piece_at(mov.start_square)?.color);
+        let side = if mov.end_square > mov.start_square {
+            KINGSIDE
+        } else {
+            QUEENSIDE
+        };
+        let back_rank = rank(mov.start_square) * 8;
+        let rook_square = back_rank + self.castling_rooks[color][side]? as usize;
+        let (king_file, rook_file) = [(6, 5), (2, 3)][side];
+        return Some([
+            (mov.start_square, back_rank + king_file),
+            (rook_square, back_rank + rook_file),
+        ]);
+    }
+
+    fn toggle_side_to_play(&mut self) {
+        self.side_to_play = self.side_to_play.inverse_color();
+        self.hash ^= SIDE_KEY;
+    }
+
+    // checks if any piece of the given color attacks the square
+    pub fn is_square_attacked(&self, square: usize, by: &Color) -> bool {
+        let occupancy = self.occupied();
+        let defender = by.inverse_color();
+        let rooks = self.pieces(by, &PieceKind::Rook) | self.pieces(by, &PieceKind::Queen);
+        let bishops = self.pieces(by, &PieceKind::Bishop) | self.pieces(by, &PieceKind::Queen);
+        return PAWN_ATTACKS[color_index(&defender)][square] & self.pieces(by, &PieceKind::Pawn)
+            != EMPTY
+            || KNIGHT_ATTACKS[square] & self.pieces(by, &PieceKind::Knight) != EMPTY
+            || KING_ATTACKS[square] & self.pieces(by, &PieceKind::King) != EMPTY
+            || rook_attacks(square, occupancy) & rooks != EMPTY
+            || bishop_attacks(square, occupancy) & bishops != EMPTY;
+    }
+
+    // checks if the king of the side to play is attacked
+    pub fn in_check(&self) -> bool {
+        let opponent = self.side_to_play.inverse_color();
+        return self
+            .king_square(&self.side_to_play)
+            .is_some_and(|square| self.is_square_attacked(square, &opponent));
+    }
+
+    // The record of a move that did not change anything yet, taking it
+    // back does nothing either
+    pub(crate) fn undo_record(&self, mov: &Move) -> Undo {
+        return Undo {
+            mov: mov.clone(),
+            moved: None,
+            captured: None,
+            en_passant_capture: None,
+            castled: None,
+            exploded: Vec::new(),
+            castling_rooks: self.castling_rooks,
+            pocket_state: None,
+            en_passant_square: self.en_passant_square,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+    }
+
+    // Puts the dropped piece of the side to play on its square, whether it
+    // is in the pocket is left to variant::Crazyhouse. Nothing happens if
+    // the square is occupied.
+    pub(crate) fn make_drop(&mut self, mov: &Move) -> Undo {
+        let mut undo = self.undo_record(mov);
+        let kind = match &mov.drop {
+            Some(kind) if !contains(self.occupied(), mov.end_square) => kind,
+            _ => return undo,
+        };
+        let piece = Piece {
+            kind: kind.clone(),
+            color: self.side_to_play.clone(),
+        };
+        self.put_piece(mov.end_square, &piece);
+        self.en_passant_square = None;
+        self.halfmove_clock += 1;
+        self.finish_move();
+        undo.moved = Some(piece);
+        return undo;
+    }
+
+    // Plays the move and returns the record needed to take it back. Drops
+    // are played by Position::make_drop.
+    pub fn make_move(&mut self, mov: &Move) -> Undo {
+        let mut undo = self.undo_record(mov);
+        let color = color_index(&self.side_to_play);
+        let piece = match self.piece_at(mov.start_square) {
+            Some(piece) => piece,
+            None => return undo,
+        };
+        if mov.castles {
+            if let Some(squares) = self.castling_squares(mov) {
+                self.castle(squares);
+                undo.castled = Some(squares);
+                self.remove_castling_right(color, KINGSIDE);
+                self.remove_castling_right(color, QUEENSIDE);
+                self.en_passant_square = None;
+                self.halfmove_clock += 1;
+                self.finish_move();
+                undo.moved = Some(piece);
+                return undo;
+            }
+        }
+        let mut end_piece = piece.clone();
+        if let Some(promotion_piece) = &mov.promotion {
+            end_piece.kind = promotion_piece.clone();
+        }
+        if piece.kind == PieceKind::King {
+            self.remove_castling_right(color, KINGSIDE);
+            self.remove_castling_right(color, QUEENSIDE);
+        }
+        // a rook leaving or being captured on its starting square loses
+        // the castling right on that side
+        for square in [mov.start_square, mov.end_square] {
+            if let Some((rook_color, side)) = self.castling_rook_at(square) {
+                self.remove_castling_right(rook_color, side);
+            }
+        }
+        self.remove_piece(mov.start_square);
+        undo.captured = self.remove_piece(mov.end_square);
+        self.put_piece(mov.end_square, &end_piece);
+        if mov.is_en_passant {
+            // the captured pawn stands next to the capturing one, on the
+            // file of the en passant square
+            let square = rank(mov.start_square) * 8 + file(mov.end_square);
+            let opponent = self.side_to_play.inverse_color();
+            if contains(self.pieces(&opponent, &PieceKind::Pawn), square) {
+                undo.en_passant_capture = self.remove_piece(square).map(|pawn| (square, pawn));
+            }
+        }
+        self.en_passant_square = None;
+        if piece.kind == PieceKind::Pawn && mov.start_square.abs_diff(mov.end_square) == 16 {
+            self.en_passant_square = Some(((mov.start_square + mov.end_square) / 2) as u8);
+        }
+        if piece.kind == PieceKind::Pawn || undo.captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.finish_move();
+        undo.moved = Some(piece);
+        return undo;
+    }
+
+    // moves king and rook, which may each end on the other's start square
+    fn castle(
+        &mut self,
+        [(king_square, king_target), (rook_square, rook_target)]: [(usize, usize); 2],
+    ) {
+        let king = self.remove_piece(king_square);
+        let rook = self.remove_piece(rook_square);
+        if let Some(king) = king {
+            self.put_piece(king_target, &king);
+        }
+        if let Some(rook) = rook {
+            self.put_piece(rook_target, &rook);
+        }
+    }
+
+    fn finish_move(&mut self) {
+        if self.side_to_play == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.toggle_side_to_play();
+    }
+
+    // Takes back the move the undo record was created for, this must be the
+    // last move made on the position
+    pub fn unmake_move(&mut self, undo: Undo) {
+        self.castling_rooks = undo.castling_rooks;
+        self.en_passant_square = undo.en_passant_square;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        let moved = match undo.moved {
+            Some(piece) => piece,
+            None => return,
+        };
+        // the piece placement below updates the hash, it is overwritten with
+        // the stored one once all pieces are back in place
+        self.side_to_play = self.side_to_play.inverse_color();
+        if undo.mov.drop.is_some() {
+            self.remove_piece(undo.mov.end_square);
+            self.hash = undo.hash;
+            return;
+        }
+        if let Some([king, rook]) = undo.castled {
+            self.castle([(king.1, king.0), (rook.1, rook.0)]);
+            self.hash = undo.hash;
+            return;
+        }
+        // revert the changes in the opposite order they were made in
+        if let Some((square, pawn)) = &undo.en_passant_capture {
+            self.put_piece(*square, pawn);
+        }
+        self.remove_piece(undo.mov.end_square);
+        if let Some(captured) = &undo.captured {
+            self.put_piece(undo.mov.end_square, captured);
+        }
+        self.put_piece(undo.mov.start_square, &moved);
+        self.hash = undo.hash;
+    }
+}
+
+// the six fields of the FEN are well formed, so the fen crate can read
+// them without repairing or panicking
+fn check_fields(fen: &str) -> Result<(), Error> {
+    let invalid = || Error::InvalidFen(fen.to_string());
+    let fields: Vec<&str> = fen.split(' ').collect();
+    let [placement, side, castling, en_passant, halfmove, fullmove] = fields[..] else {
+        return Err(invalid());
+    };
+    let castling_letter = |c: char| "KQkqABCDEFGHabcdefgh".contains(c);
+    let valid = matches!(side, "w" | "b")
+        && (castling == "-" || !castling.is_empty() && castling.chars().all(castling_letter))
+        && (en_passant == "-" || matches!(en_passant.as_bytes(), [b'a'..=b'h', b'3' | b'6']))
+        && halfmove.parse::<u64>().is_ok()
+        && fullmove.parse::<u64>().is_ok();
+    if !valid {
+        return Err(invalid());
+    }
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(invalid());
+    }
+    for rank in ranks {
+        let mut files = 0;
+        for c in rank.chars() {
+            files += match c {
+                '1'..='8' => c as usize - '0' as usize,
+                'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => 1,
+                _ => return Err(invalid()),
+            };
+        }
+        if files != 8 {
+            return Err(invalid());
+        }
+    }
+    for king in ['K', 'k'] {
+        if placement.matches(king).count() != 1 {
+            return Err(invalid());
+        }
+    }
+    return Ok(());
+}
+
+impl From<&BoardState> for Position {
+    fn from(board: &BoardState) -> Self {
+        let mut position = Self {
+            pieces: [[EMPTY; 6]; 2],
+            occupancy: [EMPTY; 2],
+            side_to_play: board.side_to_play.clone(),
+            castling_rooks: [
+                [
+                    board.white_can_oo.then_some(7),
+                    board.white_can_ooo.then_some(0),
+                ],
+                [
+                    board.black_can_oo.then_some(7),
+                    board.black_can_ooo.then_some(0),
+                ],
+            ],
+            chess960: false,
+            crazyhouse: false,
+            pockets: [[0; 5]; 2],
+            promoted: EMPTY,
+            en_passant_square: board.en_passant_square,
+            halfmove_clock: board.halfmove_clock,
+            fullmove_number: board.fullmove_number,
+            hash: 0,
+        };
+        for (square, occupant) in board.pieces.iter().enumerate() {
+            if let Some(piece) = occupant {
+                position.put_piece(square, piece);
+            }
+        }
+        if position.side_to_play == Color::Black {
+            position.hash ^= SIDE_KEY;
+        }
+        for (rooks, keys) in position.castling_rooks.iter().zip(CASTLING_KEYS) {
+            for side in [KINGSIDE, QUEENSIDE] {
+                if rooks[side].is_some() {
+                    position.hash ^= keys[side];
+                }
+            }
+        }
+        return position;
+    }
+}
+
+impl From<&Position> for BoardState {
+    fn from(position: &Position) -> Self {
+        return BoardState {
+            pieces: (0..64).map(|square| position.piece_at(square)).collect(),
+            side_to_play: position.side_to_play.clone(),
+            white_can_oo: position.castling_rooks[0][KINGSIDE].is_some(),
+            white_can_ooo: position.castling_rooks[0][QUEENSIDE].is_some(),
+            black_can_oo: position.castling_rooks[1][KINGSIDE].is_some(),
+            black_can_ooo: position.castling_rooks[1][QUEENSIDE].is_some(),
+            en_passant_square: position.en_passant_square,
+            halfmove_clock: position.halfmove_clock,
+            fullmove_number: position.fullmove_number,
+        };
+    }
+}
@@ -0,0 +1,220 @@
+use crate::move_utils::{drop_letter, drop_piece_from_letter, Move};
+use crate::pgn::GameResult;
+use crate::position::Position;
+use crate::square_utils::*;
+use crate::variant::{Standard, Variant};
+use fen::PieceKind;
+
+// Standard Algebraic Notation, e.g. "Nbd7", "exd6", "e8=Q+" or "O-O-O#",
+// and Crazyhouse drops like "P@e4"
+
+pub fn piece_letter(kind: &PieceKind) -> Option<char> {
+    match kind {
+        PieceKind::Pawn => None,
+        PieceKind::Knight => Some('N'),
+        PieceKind::Bishop => Some('B'),
+        PieceKind::Rook => Some('R'),
+        PieceKind::Queen => Some('Q'),
+        PieceKind::King => Some('K'),
+    }
+}
+
+pub fn piece_from_letter(letter: char) -> Option<PieceKind> {
+    match letter {
+        'N' => Some(PieceKind::Knight),
+        'B' => Some(PieceKind::Bishop),
+        'R' => Some(PieceKind::Rook),
+        'Q' => Some(PieceKind::Queen),
+        'K' => Some(PieceKind::King),
+        _ => None,
+    }
+}
+
+impl Position {
+    // The SAN of a legal move in this position, see Variant::move_to_san
+    // for the notation of other variants
+    pub fn move_to_san(&self, mov: &Move) -> String {
+        return move_to_san(&Standard, self, mov);
+    }
+
+    // Finds the legal move described by the SAN. Check and mate markers,
+    // annotations like "!?", "e.p.", zeros in castling, hyphens and
+    // superfluous disambiguation are accepted, ambiguous moves are not.
+    pub fn move_from_san(&self, san: &str) -> Option<Move> {
+        return move_from_san(&Standard, self, san);
+    }
+}
+
+// The SAN of a legal move by the rules of the variant, which decide which
+// moves have to be told apart and whether the move gives check or mate
+pub(crate) fn move_to_san<V: Variant + ?Sized>(
+    variant: &V,
+    position: &Position,
+    mov: &Move,
+) -> String {
+    let mut san = String::new();
+    let kind = position
+        .piece_at(mov.start_square)
+        .map_or(PieceKind::Pawn, |piece| piece.kind);
+    let is_capture = mov.is_en_passant
+        || position
+            .piece_at(mov.end_square)
+            .is_some_and(|piece| piece.color != position.side_to_play());
+    if let Some(kind) = &mov.drop {
+        san.push(drop_letter(kind));
+        san.push('@');
+        san.push_str(&square_to_string(mov.end_square));
+    } else if mov.castles {
+        if mov.end_square > mov.start_square {
+            san.push_str("O-O");
+        } else {
+            san.push_str("O-O-O");
+        }
+    } else if let Some(letter) = piece_letter(&kind) {
+        san.push(letter);
+        let legal_moves = variant.legal_moves(position);
+        san.push_str(&disambiguation(position, &legal_moves, mov, &kind));
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&square_to_string(mov.end_square));
+    } else {
+        if is_capture {
+            san.push(file_str(mov.start_square));
+            san.push('x');
+        }
+        san.push_str(&square_to_string(mov.end_square));
+        if let Some(promotion) = mov.promotion.as_ref().and_then(piece_letter) {
+            san.push('=');
+            san.push(promotion);
+        }
+    }
+    // a move that wins the game on the board, like a mate or blowing up
+    // the king in atomic chess, is marked as mate
+    let mut after_move = position.clone();
+    variant.make_move(&mut after_move, mov);
+    let legal_moves = variant.legal_moves(&after_move);
+    let decided = variant
+        .outcome(&after_move, &legal_moves)
+        .is_some_and(|status| status.result() != GameResult::Draw);
+    if decided {
+        san.push('#');
+    } else if variant.in_check(&after_move) {
+        san.push('+');
+    }
+    return san;
+}
+
+// The file and/or rank of the start square, as far as needed to tell the
+// move apart from moves of other pieces of the same kind
+fn disambiguation(
+    position: &Position,
+    legal_moves: &[Move],
+    mov: &Move,
+    kind: &PieceKind,
+) -> String {
+    let others: Vec<usize> = legal_moves
+        .iter()
+        .filter(|other| {
+            other.end_square == mov.end_square
+                && other.start_square != mov.start_square
+                && position
+                    .piece_at(other.start_square)
+                    .is_some_and(|piece| piece.kind == *kind)
+        })
+        .map(|other| other.start_square)
+        .collect();
+    if others.is_empty() {
+        return String::new();
+    }
+    if others.iter().all(|sq| file(*sq) != file(mov.start_square)) {
+        return file_str(mov.start_square).to_string();
+    }
+    if others.iter().all(|sq| rank(*sq) != rank(mov.start_square)) {
+        return rank_str(mov.start_square).to_string();
+    }
+    return square_to_string(mov.start_square);
+}
+
+// The legal move by the rules of the variant described by the SAN, see
+// Position::move_from_san
+pub(crate) fn move_from_san<V: Variant + ?Sized>(
+    variant: &V,
+    position: &Position,
+    san: &str,
+) -> Option<Move> {
+    let san = san.trim().trim_end_matches(" e.p.");
+    let san = san.trim_end_matches(['+', '#', '!', '?']);
+    let legal_moves = variant.legal_moves(position);
+
+    let castle_direction = match san {
+        "O-O" | "0-0" => Some(true),
+        "O-O-O" | "0-0-0" => Some(false),
+        _ => None,
+    };
+    if let Some(kingside) = castle_direction {
+        return legal_moves
+            .into_iter()
+            .find(|mov| mov.castles && (mov.end_square > mov.start_square) == kingside);
+    }
+
+    // drops are written "N@f3", pawn drops also as "@e4"
+    if let Some((letter, destination)) = san.split_once('@') {
+        let kind = match letter {
+            "" => PieceKind::Pawn,
+            _ => drop_piece_from_letter(letter.chars().next()?).filter(|_| letter.len() == 1)?,
+        };
+        let end_square = try_square_from_string(destination).ok()?;
+        return legal_moves
+            .into_iter()
+            .find(|mov| mov.drop == Some(kind.clone()) && mov.end_square == end_square);
+    }
+
+    let mut chars: Vec<char> = san.chars().filter(|c| *c != 'x' && *c != '-').collect();
+    let kind = match chars.first().and_then(|c| piece_from_letter(*c)) {
+        Some(kind) => {
+            chars.remove(0);
+            kind
+        }
+        None => PieceKind::Pawn,
+    };
+    // promotions are written "e8=Q" or "e8Q"
+    let mut promotion = None;
+    if kind == PieceKind::Pawn && chars.last().is_some_and(|c| c.is_ascii_alphabetic()) {
+        let letter = chars.pop()?.to_ascii_uppercase();
+        promotion = Some(piece_from_letter(letter)?);
+        if chars.last() == Some(&'=') {
+            chars.pop();
+        }
+    }
+    if chars.len() < 2 {
+        return None;
+    }
+    let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let end_square = try_square_from_string(&destination).ok()?;
+    let mut from_file = None;
+    let mut from_rank = None;
+    for c in chars {
+        match c {
+            'a'..='h' => from_file = Some(c as usize - 'a' as usize),
+            '1'..='8' => from_rank = Some(c as usize - '1' as usize),
+            _ => return None,
+        }
+    }
+
+    let mut candidates = legal_moves.into_iter().filter(|mov| {
+        !mov.castles
+            && mov.end_square == end_square
+            && mov.promotion == promotion
+            && from_file.is_none_or(|f| file(mov.start_square) == f)
+            && from_rank.is_none_or(|r| rank(mov.start_square) == r)
+            && position
+                .piece_at(mov.start_square)
+                .is_some_and(|piece| piece.kind == kind)
+    });
+    let mov = candidates.next()?;
+    if candidates.next().is_some() {
+        return None;
+    }
+    return Some(mov);
+}
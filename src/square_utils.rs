@@ -1,3 +1,5 @@
+use crate::Error;
+
 // increase the rank of the square by the given amount
 pub fn add_rank(square: usize, increment: i32) -> i32 {
     return square as i32 + increment * 8;
@@ -16,24 +18,33 @@ pub fn file(square: usize) -> usize {
 }
 
 pub fn rank_str(square: usize) -> char {
-    return (b'1' as u8 + rank(square) as u8) as char;
+    return (b'1' + rank(square) as u8) as char;
 }
 
 pub fn file_str(square: usize) -> char {
-    return (b'a' as u8 + file(square) as u8) as char;
+    return (b'a' + file(square) as u8) as char;
 }
 
 pub fn is_on_board(rank: i32, file: i32) -> bool {
-    let rank_on_board = 0 <= rank && rank < 8;
-    let file_on_board = 0 <= file && file < 8;
+    let rank_on_board = (0..8).contains(&rank);
+    let file_on_board = (0..8).contains(&file);
     return rank_on_board && file_on_board;
 }
 
+// The index of a square like "e4", panics if the name is not a valid square
 pub fn square_from_string(square_str: &str) -> usize {
-    let mut square: u8 = 0;
-    square += (square_str.chars().collect::<Vec<char>>()[0] as u8) - b'a';
-    square += ((square_str.chars().collect::<Vec<char>>()[1] as u8) - b'1') * 8;
-    return square as usize;
+    return try_square_from_string(square_str).expect("Chesslib was given an invalid square");
+}
+
+pub fn try_square_from_string(square_str: &str) -> Result<usize, Error> {
+    let mut chars = square_str.chars();
+    let file = chars.next().filter(|c| ('a'..='h').contains(c));
+    let rank = chars.next().filter(|c| ('1'..='8').contains(c));
+    let (file, rank) = match (file, rank, chars.next()) {
+        (Some(file), Some(rank), None) => (file, rank),
+        _ => return Err(Error::InvalidSquare(square_str.to_string())),
+    };
+    return Ok((rank as usize - '1' as usize) * 8 + file as usize - 'a' as usize);
 }
 
 pub fn square_to_string(square: usize) -> String {
@@ -0,0 +1,363 @@
+use crate::bitboard::*;
+use crate::end_state::{
+    atomic_insufficient_material, exploded_king_winner, insufficient_material, GameStatus,
+};
+use crate::move_generation::MoveGeneration;
+use crate::move_utils::{Move, ToggleColor};
+use crate::perft::variant_perft;
+use crate::position::{Position, Undo, KINGSIDE, POCKET_KINDS, QUEENSIDE};
+use crate::san;
+use crate::{Error, STARTING_POSITION};
+use fen::{Color, PieceKind};
+use std::sync::Arc;
+
+// The rules a Game is played by. Every method defaults to standard chess,
+// so a variant only overrides the rules it changes.
+pub trait Variant: Send + Sync {
+    // the value of the PGN Variant tag
+    fn name(&self) -> &'static str;
+
+    fn starting_position(&self) -> Position {
+        return self
+            .position_from_fen(STARTING_POSITION)
+            .expect("the starting position is valid");
+    }
+
+    fn position_from_fen(&self, fen: &str) -> Result<Position, Error> {
+        return Position::try_from_fen(fen);
+    }
+
+    fn legal_moves(&self, position: &Position) -> Vec<Move> {
+        return position.legal_moves();
+    }
+
+    // The moves the pieces can make without regard to the own king, used to
+    // tell why a move is not legal. Castling is not included.
+    fn pseudo_legal_moves(&self, position: &Position) -> Vec<Move> {
+        return position.generate_moves(false);
+    }
+
+    fn make_move(&self, position: &mut Position, mov: &Move) -> Undo {
+        return position.make_move(mov);
+    }
+
+    fn unmake_move(&self, position: &mut Position, undo: Undo) {
+        position.unmake_move(undo);
+    }
+
+    // checks if the king of the side to play is attacked
+    fn in_check(&self, position: &Position) -> bool {
+        return position.in_check();
+    }
+
+    // The key of the position for the repetition rules, see
+    // Position::repetition_key
+    fn repetition_key(&self, position: &Position) -> u64 {
+        return position.repetition_key_by(|| self.legal_moves(position));
+    }
+
+    // How the game ended on the board, None if it goes on. Repetitions, the
+    // seventy-five move rule and the clock are left to the Game.
+    fn outcome(&self, position: &Position, legal_moves: &[Move]) -> Option<GameStatus> {
+        if legal_moves.is_empty() {
+            return Some(mate_or_stalemate(position, self.in_check(position)));
+        }
+        if insufficient_material(position) {
+            return Some(GameStatus::Insufficient);
+        }
+        return None;
+    }
+
+    fn move_to_san(&self, position: &Position, mov: &Move) -> String {
+        return san::move_to_san(self, position, mov);
+    }
+
+    fn move_from_san(&self, position: &Position, san: &str) -> Option<Move> {
+        return san::move_from_san(self, position, san);
+    }
+
+    // Position::perft by the rules of the variant
+    fn perft(&self, position: &Position, depth: usize) -> u64 {
+        let mut position = position.clone();
+        return variant_perft(self, &mut position, depth);
+    }
+}
+
+// the end of the game when the side to play has no legal move
+fn mate_or_stalemate(position: &Position, in_check: bool) -> GameStatus {
+    if in_check {
+        return GameStatus::Checkmate(position.side_to_play().inverse_color());
+    }
+    return GameStatus::Stalemate;
+}
+
+// Chess as played by FIDE rules, Chess960 positions included
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        return "Standard";
+    }
+}
+
+// Captured pieces change sides and go to the capturing player's pocket,
+// from where they can be dropped instead of making a move
+pub struct Crazyhouse;
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        return "Crazyhouse";
+    }
+
+    fn position_from_fen(&self, fen: &str) -> Result<Position, Error> {
+        return Position::from_crazyhouse_fen(fen);
+    }
+
+    // A drop can not uncover a check, only block one, so drops are only
+    // tried on the board while the king is in check
+    fn legal_moves(&self, position: &Position) -> Vec<Move> {
+        let mut moves = position.legal_moves();
+        let mut drops = drop_moves(position);
+        if position.in_check() {
+            let mut simulation_board = position.clone();
+            drops.retain(|mov| {
+                let undo = simulation_board.make_drop(mov);
+                let legal = !simulation_board.player_in_check();
+                simulation_board.unmake_move(undo);
+                legal
+            });
+        }
+        moves.append(&mut drops);
+        return moves;
+    }
+
+    fn pseudo_legal_moves(&self, position: &Position) -> Vec<Move> {
+        let mut moves = position.generate_moves(false);
+        moves.append(&mut drop_moves(position));
+        return moves;
+    }
+
+    // Dropped pieces leave the pocket, captured pieces go to the pocket of
+    // the capturing player and promoted pieces go there as pawns
+    fn make_move(&self, position: &mut Position, mov: &Move) -> Undo {
+        let side_to_play = position.side_to_play();
+        let color = color_index(&side_to_play);
+        let pocket_state = position.pocket_state();
+        let (_, promoted) = pocket_state;
+        if let Some(kind) = &mov.drop {
+            let count = position.pocket(&side_to_play, kind);
+            if count == 0 || contains(position.occupied(), mov.end_square) {
+                return position.undo_record(mov);
+            }
+            let mut undo = position.make_drop(mov);
+            position.set_pocket(color, kind_index(kind), count - 1);
+            undo.pocket_state = Some(pocket_state);
+            return undo;
+        }
+        let mut undo = position.make_move(mov);
+        if let Some((square, piece)) = undo.captured() {
+            let kind = match piece.kind {
+                _ if contains(promoted, square) => Some(PieceKind::Pawn),
+                // only happens when trying moves in a position that is not legal
+                PieceKind::King => None,
+                kind => Some(kind),
+            };
+            if let Some(kind) = kind {
+                let count = position.pocket(&side_to_play, &kind);
+                position.set_pocket(color, kind_index(&kind), (count + 1).min(16));
+            }
+        }
+        let mut now_promoted = promoted & !square_bb(mov.start_square) & !square_bb(mov.end_square);
+        if contains(promoted, mov.start_square) || mov.promotion.is_some() {
+            now_promoted |= square_bb(mov.end_square);
+        }
+        position.set_promoted(now_promoted);
+        undo.pocket_state = Some(pocket_state);
+        return undo;
+    }
+
+    fn unmake_move(&self, position: &mut Position, undo: Undo) {
+        let pocket_state = undo.pocket_state;
+        position.unmake_move(undo);
+        if let Some(pocket_state) = pocket_state {
+            position.restore_pocket_state(pocket_state);
+        }
+    }
+
+    // Pieces never leave the game, only two bare kings with empty pockets
+    // can not mate
+    fn outcome(&self, position: &Position, legal_moves: &[Move]) -> Option<GameStatus> {
+        let kings = position.pieces(&Color::White, &PieceKind::King)
+            | position.pieces(&Color::Black, &PieceKind::King);
+        let empty_pockets = [Color::White, Color::Black].iter().all(|color| {
+            POCKET_KINDS
+                .iter()
+                .all(|kind| position.pocket(color, kind) == 0)
+        });
+        if !legal_moves.is_empty() && position.occupied() == kings && empty_pockets {
+            return Some(GameStatus::Insufficient);
+        }
+        return match Standard.outcome(position, legal_moves) {
+            Some(GameStatus::Insufficient) => None,
+            outcome => outcome,
+        };
+    }
+}
+
+// every drop of a piece from the pocket of the side to play onto an empty
+// square, pawns can not be dropped on the first or last rank
+fn drop_moves(position: &Position) -> Vec<Move> {
+    let mut moves = Vec::new();
+    let side_to_play = position.side_to_play();
+    let empty = !position.occupied();
+    for kind in POCKET_KINDS {
+        if position.pocket(&side_to_play, &kind) == 0 {
+            continue;
+        }
+        let targets = match kind {
+            PieceKind::Pawn => empty & !RANK_1 & !RANK_8,
+            _ => empty,
+        };
+        for square in squares(targets) {
+            moves.push(Move::drop_piece(kind.clone(), square));
+        }
+    }
+    return moves;
+}
+
+// Captures blow up the capturing piece and every piece but pawns around the
+// capture square, the player who blows up the enemy king wins
+pub struct Atomic;
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        return "Atomic";
+    }
+
+    // The player who moved must not blow up the own king and must not leave
+    // it in check, unless the move blew up the enemy king, which ends the
+    // game at once
+    fn legal_moves(&self, position: &Position) -> Vec<Move> {
+        if exploded_king_winner(position).is_some() {
+            return Vec::new();
+        }
+        let mut moves = self.pseudo_legal_moves(position);
+        let side_to_play = position.side_to_play();
+        if let (Some(king), Some(enemy_king)) = (
+            position.king_square(&side_to_play),
+            position.king_square(&side_to_play.inverse_color()),
+        ) {
+            // the squares next to the enemy king can not be in check
+            let safe = KING_ATTACKS[enemy_king];
+            moves.append(&mut position.castling_moves_with_safe_squares(king, safe));
+        }
+        // every move is tried on the same scratch copy and taken back again
+        let mut simulation_board = position.clone();
+        moves.retain(|mov| {
+            let undo = self.make_move(&mut simulation_board, mov);
+            let mover = simulation_board.side_to_play().inverse_color();
+            let legal = simulation_board.king_square(&mover).is_some()
+                && (exploded_king_winner(&simulation_board).is_some()
+                    || kings_adjacent(&simulation_board)
+                    || !simulation_board.player_in_check());
+            self.unmake_move(&mut simulation_board, undo);
+            legal
+        });
+        return moves;
+    }
+
+    // kings do not capture
+    fn pseudo_legal_moves(&self, position: &Position) -> Vec<Move> {
+        let mut moves = position.generate_moves(false);
+        let kings = position.pieces(&position.side_to_play(), &PieceKind::King);
+        moves.retain(|mov| {
+            !contains(kings, mov.start_square) || !contains(position.occupied(), mov.end_square)
+        });
+        return moves;
+    }
+
+    // A capture blows up the capturing piece and every piece but pawns next
+    // to the capture square, castling rights go with the exploded kings and
+    // rooks
+    fn make_move(&self, position: &mut Position, mov: &Move) -> Undo {
+        let mut undo = position.make_move(mov);
+        if undo.captured().is_none() {
+            return undo;
+        }
+        let pawns = position.pieces(&Color::White, &PieceKind::Pawn)
+            | position.pieces(&Color::Black, &PieceKind::Pawn);
+        let square = mov.end_square;
+        let blast = (square_bb(square) | KING_ATTACKS[square] & !pawns) & position.occupied();
+        for target in squares(blast) {
+            if let Some((color, side)) = position.castling_rook_at(target) {
+                position.remove_castling_right(color, side);
+            }
+            if let Some(piece) = position.remove_piece(target) {
+                if piece.kind == PieceKind::King {
+                    let color = color_index(&piece.color);
+                    position.remove_castling_right(color, KINGSIDE);
+                    position.remove_castling_right(color, QUEENSIDE);
+                }
+                undo.exploded.push((target, piece));
+            }
+        }
+        return undo;
+    }
+
+    fn unmake_move(&self, position: &mut Position, mut undo: Undo) {
+        // the blast is taken back before the move itself
+        for (square, piece) in std::mem::take(&mut undo.exploded) {
+            position.put_piece(square, &piece);
+        }
+        position.unmake_move(undo);
+    }
+
+    // Kings standing next to each other can not be in check, as capturing
+    // one king would blow up the other one too
+    fn in_check(&self, position: &Position) -> bool {
+        return !kings_adjacent(position) && position.in_check();
+    }
+
+    fn outcome(&self, position: &Position, legal_moves: &[Move]) -> Option<GameStatus> {
+        if let Some(winner) = exploded_king_winner(position) {
+            return Some(GameStatus::KingExploded(winner));
+        }
+        if legal_moves.is_empty() {
+            return Some(mate_or_stalemate(position, self.in_check(position)));
+        }
+        if atomic_insufficient_material(position) {
+            return Some(GameStatus::Insufficient);
+        }
+        return None;
+    }
+}
+
+fn kings_adjacent(position: &Position) -> bool {
+    return match (
+        position.king_square(&Color::White),
+        position.king_square(&Color::Black),
+    ) {
+        (Some(white), Some(black)) => contains(KING_ATTACKS[white], black),
+        _ => false,
+    };
+}
+
+// the PGN Variant tags of Chess960, which is played by the Standard rules
+const CHESS960_NAMES: [&str; 2] = ["chess960", "fischerandom"];
+
+// checks if the PGN Variant tag names Chess960, whose FEN castling field
+// is read as X-FEN
+pub fn is_chess960_name(name: &str) -> bool {
+    return CHESS960_NAMES.contains(&name.to_ascii_lowercase().as_str());
+}
+
+// The variant with the given PGN Variant tag, None if it is not supported
+pub fn from_name(name: &str) -> Option<Arc<dyn Variant>> {
+    match name.to_ascii_lowercase().as_str() {
+        "standard" | "chess" => Some(Arc::new(Standard)),
+        _ if is_chess960_name(name) => Some(Arc::new(Standard)),
+        "crazyhouse" => Some(Arc::new(Crazyhouse)),
+        "atomic" => Some(Arc::new(Atomic)),
+        _ => None,
+    }
+}
@@ -0,0 +1,67 @@
+// Random keys for Zobrist hashing. A position's hash is the xor of the keys
+// of every feature present in it, so making a move only has to xor out the
+// keys of what changed and xor in the new ones.
+
+// indexed by color, piece kind and square
+pub const PIECE_KEYS: [[[u64; 64]; 6]; 2] = piece_keys();
+// present iff black is to play
+pub const SIDE_KEY: u64 = splitmix64(768);
+// indexed by color, then by KINGSIDE or QUEENSIDE
+pub const CASTLING_KEYS: [[u64; 2]; 2] = [
+    [splitmix64(769), splitmix64(770)],
+    [splitmix64(771), splitmix64(772)],
+];
+// indexed by the file of the en passant square
+pub const EN_PASSANT_KEYS: [u64; 8] = en_passant_keys();
+// indexed by color, piece kind and the number of such pieces in the
+// Crazyhouse pocket, an empty pocket has no key
+pub const POCKET_KEYS: [[[u64; 17]; 5]; 2] = pocket_keys();
+// indexed by the square of a piece that was a pawn before it promoted
+pub const PROMOTED_KEYS: [u64; 64] = promoted_keys();
+
+const fn splitmix64(index: u64) -> u64 {
+    let mut z = (index + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    return z ^ (z >> 31);
+}
+
+const fn piece_keys() -> [[[u64; 64]; 6]; 2] {
+    let mut keys = [[[0; 64]; 6]; 2];
+    let mut index = 0;
+    while index < 768 {
+        keys[index / 384][index / 64 % 6][index % 64] = splitmix64(index as u64);
+        index += 1;
+    }
+    return keys;
+}
+
+const fn en_passant_keys() -> [u64; 8] {
+    let mut keys = [0; 8];
+    let mut file = 0;
+    while file < 8 {
+        keys[file] = splitmix64(773 + file as u64);
+        file += 1;
+    }
+    return keys;
+}
+
+const fn pocket_keys() -> [[[u64; 17]; 5]; 2] {
+    let mut keys = [[[0; 17]; 5]; 2];
+    let mut index = 0;
+    while index < 170 {
+        keys[index / 85][index / 17 % 5][index % 17] = splitmix64(781 + index as u64);
+        index += 1;
+    }
+    return keys;
+}
+
+const fn promoted_keys() -> [u64; 64] {
+    let mut keys = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        keys[square] = splitmix64(951 + square as u64);
+        square += 1;
+    }
+    return keys;
+}
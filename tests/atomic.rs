@@ -0,0 +1,92 @@
+#![allow(clippy::needless_return)]
+
+use chesslib::end_state::GameStatus;
+use chesslib::move_utils::Move;
+use chesslib::pgn::GameResult;
+use chesslib::variant::{self, Atomic, Variant};
+use chesslib::{Color, Game, IllegalMove};
+use std::sync::Arc;
+
+mod utils;
+
+use utils::mov;
+
+fn atomic_game(fen: &str) -> Game {
+    return Game::try_variant_from_fen(Arc::new(Atomic), fen).unwrap();
+}
+
+#[test]
+fn captures_explode() {
+    let fen = "4k3/8/2n1b3/3q4/2P1P3/8/8/4K2R w K - 0 1";
+    let mut game = atomic_game(fen);
+    let outcome = game.try_play(&mov("c4d5")).unwrap();
+    assert!(outcome.captured.is_some());
+    // the pawn on e4 survives the blast, the knight and bishop do not
+    assert_eq!(game.board.to_fen(), "4k3/8/8/8/4P3/8/8/4K2R b K - 0 1");
+    game.undo();
+    assert_eq!(game.board.to_fen(), fen);
+}
+
+#[test]
+fn kings_do_not_capture() {
+    // taking the queen would blow up the own king as well
+    let game = atomic_game("4k3/8/8/8/8/8/4q3/4K3 w - - 0 1");
+    assert_eq!(game.status(), GameStatus::Checkmate(Color::Black));
+    let mut game = atomic_game("4k3/8/8/8/8/8/8/3rK3 w - - 0 1");
+    assert_eq!(
+        game.try_play(&mov("e1d1")),
+        Err(IllegalMove::InvalidMovement)
+    );
+}
+
+#[test]
+fn adjacent_kings_are_not_in_check() {
+    let position = Atomic
+        .position_from_fen("8/8/8/8/8/8/3kK2r/8 w - - 0 1")
+        .unwrap();
+    assert!(!Atomic.in_check(&position));
+    assert!(position.in_check());
+    // leaving the other king is only possible out of reach of the rook
+    let king_moves: Vec<Move> = Atomic.legal_moves(&position);
+    assert!(king_moves.contains(&mov("e2f1")));
+    assert!(!king_moves.contains(&mov("e2f2")));
+}
+
+#[test]
+fn blowing_up_the_king_wins() {
+    let mut game = atomic_game("4k3/4r3/8/8/8/8/8/4R1K1 w - - 0 1");
+    game.try_play(&mov("e1e7")).unwrap();
+    assert_eq!(game.status(), GameStatus::KingExploded(Color::White));
+    assert_eq!(game.status().result(), GameResult::WhiteWins);
+    assert!(game.legal_moves().is_empty());
+
+    // a capture next to the own king would blow it up as well
+    let game = atomic_game("8/8/8/8/8/8/3r4/3K3k w - - 0 1");
+    assert!(!game.legal_moves().contains(&mov("d1d2")));
+}
+
+#[test]
+fn atomic_by_name() {
+    assert_eq!(variant::from_name("Atomic").unwrap().name(), "Atomic");
+    assert_eq!(
+        atomic_game("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").status(),
+        GameStatus::Insufficient
+    );
+    assert_eq!(
+        atomic_game("4k3/8/8/8/8/8/8/4KR2 w - - 0 1").status(),
+        GameStatus::Ongoing
+    );
+}
+
+#[test]
+fn blowing_up_the_king_is_written_as_mate() {
+    let game = atomic_game("4k3/4r3/8/8/8/8/8/4R1K1 w - - 0 1");
+    assert_eq!(Atomic.move_to_san(&game.board, &mov("e1e7")), "Rxe7#");
+    assert_eq!(
+        Atomic.move_from_san(&game.board, "Rxe7#"),
+        Some(mov("e1e7"))
+    );
+    // out of reach of the blast the capture decides nothing
+    let game = atomic_game("6k1/4r3/8/8/8/8/8/4R1K1 w - - 0 1");
+    assert_eq!(Atomic.move_to_san(&game.board, &mov("e1e7")), "Rxe7");
+}
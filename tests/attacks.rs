@@ -0,0 +1,57 @@
+use chesslib::attacks::*;
+use chesslib::bitboard::*;
+use chesslib::square_utils::square_from_string;
+use fen::{Color, Piece, PieceKind};
+
+#[test]
+fn rook_attacks_stop_at_blockers() {
+    let occupancy = square_bb(square_from_string("d6")) | square_bb(square_from_string("f4"));
+    let piece = Piece {
+        kind: PieceKind::Rook,
+        color: Color::White,
+    };
+    let expected = ["d5", "d6", "d3", "d2", "d1", "e4", "f4", "c4", "b4", "a4"]
+        .iter()
+        .fold(EMPTY, |bb, square| {
+            bb | square_bb(square_from_string(square))
+        });
+    assert_eq!(
+        attacks_from(square_from_string("d4"), &piece, occupancy),
+        expected
+    );
+}
+
+#[test]
+fn black_pawn_attacks_downwards() {
+    let piece = Piece {
+        kind: PieceKind::Pawn,
+        color: Color::Black,
+    };
+    let expected = square_bb(square_from_string("d4")) | square_bb(square_from_string("f4"));
+    assert_eq!(
+        attacks_from(square_from_string("e5"), &piece, EMPTY),
+        expected
+    );
+}
+
+// compare the table lookups against stepping along the rays
+#[test]
+fn sliding_lookups_match_ray_stepping() {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    for _ in 0..2000 {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        let occupancy = seed & (seed >> 3);
+        for square in 0..64 {
+            assert_eq!(
+                rook_attacks(square, occupancy),
+                sliding_attacks(square, occupancy, &ROOK_DIRECTIONS)
+            );
+            assert_eq!(
+                bishop_attacks(square, occupancy),
+                sliding_attacks(square, occupancy, &BISHOP_DIRECTIONS)
+            );
+        }
+    }
+}
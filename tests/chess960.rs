@@ -0,0 +1,182 @@
+use chesslib::chess960::*;
+use chesslib::move_generation::MoveGeneration;
+use chesslib::move_utils::Move;
+use chesslib::pgn::read_game;
+use chesslib::position::{Position, KINGSIDE, QUEENSIDE};
+use chesslib::square_utils::square_from_string;
+use chesslib::{Color, Game, PieceKind};
+use std::collections::HashSet;
+
+#[test]
+fn starting_positions() {
+    assert_eq!(
+        starting_fen(518).unwrap(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+    assert_eq!(
+        starting_fen(0).unwrap(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+    );
+    assert_eq!(starting_fen(POSITIONS), None);
+
+    let mut seen = HashSet::new();
+    for index in 0..POSITIONS {
+        let rank = back_rank(index).unwrap();
+        let files =
+            |kind: PieceKind| -> Vec<usize> { (0..8).filter(|file| rank[*file] == kind).collect() };
+        let bishops = files(PieceKind::Bishop);
+        let rooks = files(PieceKind::Rook);
+        let king = files(PieceKind::King)[0];
+        assert_ne!(bishops[0] % 2, bishops[1] % 2, "{}", index);
+        assert!(rooks[0] < king && king < rooks[1], "{}", index);
+        assert!(seen.insert(starting_fen(index).unwrap()));
+    }
+}
+
+#[test]
+fn standard_start_as_chess960() {
+    let position = Position::chess960_start(518).unwrap();
+    assert!(position.is_chess960());
+    let castle = Move {
+        start_square: square_from_string("e1"),
+        end_square: square_from_string("h1"),
+        promotion: None,
+        is_en_passant: false,
+        castles: true,
+        drop: None,
+    };
+    let mut position = Position::from_chess960_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    assert!(position.legal_moves().contains(&castle));
+    position.make_move(&castle);
+    assert_eq!(position.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+    assert!(!Game::new().board.is_chess960());
+}
+
+#[test]
+fn shredder_and_x_fen() {
+    let position =
+        Position::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9")
+            .unwrap();
+    assert!(position.is_chess960());
+    assert_eq!(
+        position.castling_rook_file(&Color::White, KINGSIDE),
+        Some(7)
+    );
+    assert_eq!(
+        position.castling_rook_file(&Color::White, QUEENSIDE),
+        Some(5)
+    );
+    assert_eq!(
+        position.to_fen(),
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+    );
+    // a rook that is not the outermost one is named by its file
+    let inner = Position::from_fen("4k3/8/8/8/8/8/8/1R2K1RR w G - 0 1").unwrap();
+    assert_eq!(inner.castling_rook_file(&Color::White, KINGSIDE), Some(6));
+    assert_eq!(inner.to_fen(), "4k3/8/8/8/8/8/8/1R2K1RR w G - 0 1");
+    let same = Position::from_fen("4k3/8/8/8/8/8/8/1R2K1RR w H - 0 1").unwrap();
+    assert_eq!(same.to_fen(), "4k3/8/8/8/8/8/8/1R2K1RR w K - 0 1");
+}
+
+// king on b1 and rook on a1 end up on c1 and d1
+#[test]
+fn castling_with_king_next_to_rook() {
+    let mut position = Position::from_fen("1k6/8/8/8/8/8/8/RK5R w HA - 0 1").unwrap();
+    let queenside = Move {
+        start_square: square_from_string("b1"),
+        end_square: square_from_string("a1"),
+        promotion: None,
+        is_en_passant: false,
+        castles: true,
+        drop: None,
+    };
+    assert!(position.legal_moves().contains(&queenside));
+    let before = position.clone();
+    let undo = position.make_move(&queenside);
+    assert_eq!(position.to_fen(), "1k6/8/8/8/8/8/8/2KR3R b - - 1 1");
+    position.unmake_move(undo);
+    assert_eq!(position, before);
+    // SAN and long algebraic notation of the castling move
+    assert_eq!(before.move_to_san(&queenside), "O-O-O");
+    assert_eq!(before.move_from_san("O-O-O"), Some(queenside.clone()));
+    let board = fen::BoardState::from(&before);
+    assert_eq!(
+        Move::from_long_algebraic("b1a1".to_string(), &board),
+        queenside
+    );
+}
+
+// the rook on the king's target square blocks castling, the king on the
+// rook's target square does not
+#[test]
+fn castling_path() {
+    let blocked = Position::from_fen("4k3/8/8/8/8/8/8/4KRNR w H - 0 1").unwrap();
+    assert!(!blocked.legal_moves().iter().any(|mov| mov.castles));
+    let free = Position::from_fen("4k3/8/8/8/8/8/8/5KR1 w G - 0 1").unwrap();
+    let castle = free
+        .legal_moves()
+        .into_iter()
+        .find(|mov| mov.castles)
+        .unwrap();
+    let mut after = free.clone();
+    after.make_move(&castle);
+    assert_eq!(after.to_fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+}
+
+#[test]
+fn chess960_pgn_round_trip() {
+    let mut game = Game::start_chess960(518).unwrap();
+    for san in ["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5", "O-O"] {
+        let mov = game.board.move_from_san(san).unwrap();
+        game.execute_move(&mov);
+    }
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Variant \"Chess960\"]\n[SetUp \"1\"]\n"));
+    let reread = read_game(&pgn).unwrap();
+    assert!(reread.game.board.is_chess960());
+    assert_eq!(
+        reread.moves.last().map(|mov| mov.end_square),
+        Some(square_from_string("h1"))
+    );
+    assert_eq!(reread.game.board, game.board);
+}
+
+// the Fischerandom alias reads KQkq as X-FEN like the Chess960 tag
+#[test]
+fn fischerandom_pgn_tag() {
+    let pgn = "[Variant \"Fischerandom\"]\n[SetUp \"1\"]\n\
+        [FEN \"bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9\"]\n\n*";
+    let game = read_game(pgn).unwrap().game;
+    assert!(game.board.is_chess960());
+    assert_eq!(
+        game.board.castling_rook_file(&Color::White, QUEENSIDE),
+        Some(5)
+    );
+    assert_eq!(
+        game.board.castling_rook_file(&Color::Black, KINGSIDE),
+        Some(7)
+    );
+}
+
+// KQkq only makes a Chess960 position when asked for one
+#[test]
+fn castling_rights_off_the_standard_squares() {
+    let fen = "4k3/8/8/8/8/8/8/R4K1R w KQ - 0 1";
+    let position = Position::from_fen(fen).unwrap();
+    assert!(!position.is_chess960());
+    assert!(!position.can_castle(&Color::White, KINGSIDE));
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/R4K1R w - - 0 1");
+    assert!(Position::try_from_fen(fen).is_err());
+    assert!(Game::try_from_fen(fen).is_err());
+    assert!(Position::try_from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1").is_err());
+
+    let chess960 = Position::from_chess960_fen(fen).unwrap();
+    assert!(chess960.is_chess960());
+    assert_eq!(
+        chess960.castling_rook_file(&Color::White, KINGSIDE),
+        Some(7)
+    );
+    assert!(Position::try_from_fen("4k3/8/8/8/8/8/8/R4K1R w HA - 0 1")
+        .unwrap()
+        .is_chess960());
+}
@@ -0,0 +1,191 @@
+use chesslib::clock::*;
+use chesslib::end_state::GameStatus;
+use chesslib::move_utils::Move;
+use chesslib::square_utils::square_from_string;
+use chesslib::{Color, Game};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn secs(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+}
+
+fn started_clock(control: TimeControl) -> (Clock, ManualTime) {
+    let time = ManualTime::new();
+    let mut clock = Clock::new(control, Arc::new(time.clone()));
+    clock.start(Color::White);
+    (clock, time)
+}
+
+#[test]
+fn sudden_death() {
+    let (mut clock, time) = started_clock(TimeControl::sudden_death(secs(60)));
+    time.advance(secs(10));
+    assert_eq!(clock.remaining(&Color::White), secs(50));
+    assert!(clock.press());
+    assert_eq!(clock.running(), Some(Color::Black));
+    time.advance(secs(60));
+    assert_eq!(clock.remaining(&Color::Black), Duration::ZERO);
+    assert_eq!(clock.flag(), Some(Color::Black));
+    assert!(!clock.press());
+    assert_eq!(clock.remaining(&Color::White), secs(50));
+}
+
+#[test]
+fn fischer_increment() {
+    let (mut clock, time) = started_clock(TimeControl::fischer(secs(60), secs(5)));
+    time.advance(secs(10));
+    clock.press();
+    assert_eq!(clock.remaining(&Color::White), secs(55));
+}
+
+#[test]
+fn bronstein_delay() {
+    let (mut clock, time) = started_clock(TimeControl::bronstein(secs(60), secs(5)));
+    time.advance(secs(3));
+    clock.press();
+    assert_eq!(clock.remaining(&Color::White), secs(60));
+    time.advance(secs(10));
+    clock.press();
+    assert_eq!(clock.remaining(&Color::Black), secs(55));
+}
+
+#[test]
+fn simple_delay() {
+    let (mut clock, time) = started_clock(TimeControl::simple_delay(secs(60), secs(5)));
+    time.advance(secs(4));
+    assert_eq!(clock.remaining(&Color::White), secs(60));
+    time.advance(secs(6));
+    assert_eq!(clock.remaining(&Color::White), secs(55));
+    clock.press();
+    assert_eq!(clock.remaining(&Color::White), secs(55));
+    // the delay is not counted towards the flag
+    time.advance(secs(64));
+    assert_eq!(clock.flag(), None);
+    time.advance(secs(1));
+    assert_eq!(clock.flag(), Some(Color::Black));
+}
+
+// 40 moves in 90 minutes, then 30 minutes for the rest of the game, with
+// 30 seconds added from the first move on
+#[test]
+fn multi_stage_control() {
+    let increment = TimeBonus::Fischer(secs(30));
+    let control = TimeControl::new(vec![
+        TimeControlStage {
+            moves: Some(40),
+            time: secs(90 * 60),
+            bonus: increment.clone(),
+        },
+        TimeControlStage {
+            moves: None,
+            time: secs(30 * 60),
+            bonus: increment,
+        },
+    ])
+    .unwrap();
+    assert_eq!(control.stages().len(), 2);
+    let (mut clock, _) = started_clock(control);
+    for _ in 0..39 {
+        clock.press();
+        clock.press();
+    }
+    assert_eq!(clock.moves_to_go(&Color::White), Some(1));
+    clock.press();
+    assert_eq!(clock.moves_to_go(&Color::White), None);
+    assert_eq!(
+        clock.remaining(&Color::White),
+        secs(90 * 60 + 40 * 30 + 30 * 60)
+    );
+}
+
+// a clock needs a stage to take its starting time from
+#[test]
+fn empty_time_control() {
+    assert_eq!(TimeControl::new(Vec::new()), None);
+}
+
+#[test]
+fn repeating_moves_to_go() {
+    let (mut clock, time) = started_clock(TimeControl::moves_to_go(2, secs(60)));
+    time.advance(secs(20));
+    clock.press();
+    clock.press();
+    assert_eq!(clock.moves_to_go(&Color::White), Some(1));
+    clock.press();
+    assert_eq!(clock.moves_to_go(&Color::White), Some(2));
+    assert_eq!(clock.remaining(&Color::White), secs(100));
+}
+
+#[test]
+fn flag_ends_game() {
+    let time = ManualTime::new();
+    let mut game = Game::new();
+    game.set_clock(Clock::new(
+        TimeControl::sudden_death(secs(60)),
+        Arc::new(time.clone()),
+    ));
+    let e4 = Move::standard(square_from_string("e2"), square_from_string("e4"));
+    game.try_play(&e4).unwrap();
+    time.advance(secs(61));
+    assert_eq!(game.status(), GameStatus::Timeout(Some(Color::White)));
+    assert!(game
+        .to_pgn()
+        .contains("[Result \"1-0\"]\n[Termination \"time forfeit\"]"));
+    let e5 = Move::standard(square_from_string("e7"), square_from_string("e5"));
+    assert_eq!(game.try_play(&e5), Err(chesslib::IllegalMove::GameOver));
+}
+
+#[test]
+fn flag_against_lone_king_is_a_draw() {
+    let time = ManualTime::new();
+    let mut game = Game::start_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    game.set_clock(Clock::new(
+        TimeControl::sudden_death(secs(60)),
+        Arc::new(time.clone()),
+    ));
+    time.advance(secs(60));
+    assert_eq!(game.status(), GameStatus::Timeout(None));
+}
+
+#[test]
+fn flag_against_knight_or_bishop_is_a_draw() {
+    // the pawn keeps the material on the board sufficient
+    for fen in [
+        "4k3/8/8/8/8/8/4P3/4K1n1 w - - 0 1",
+        "4k1b1/8/8/8/8/8/4P3/4K3 w - - 0 1",
+    ] {
+        let time = ManualTime::new();
+        let mut game = Game::start_from_fen(fen);
+        game.set_clock(Clock::new(
+            TimeControl::sudden_death(secs(60)),
+            Arc::new(time.clone()),
+        ));
+        assert_eq!(game.status(), GameStatus::Ongoing);
+        time.advance(secs(60));
+        assert_eq!(game.status(), GameStatus::Timeout(None));
+    }
+}
+
+#[test]
+fn mate_after_the_flag_fell_loses_on_time() {
+    let time = ManualTime::new();
+    let mut game = Game::start_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    game.set_clock(Clock::new(
+        TimeControl::sudden_death(secs(60)),
+        Arc::new(time.clone()),
+    ));
+    let mate = Move::standard(square_from_string("a1"), square_from_string("a8"));
+    time.advance(secs(61));
+    game.execute_move(&mate);
+    assert_eq!(game.status(), GameStatus::Timeout(Some(Color::Black)));
+
+    let mut game = Game::start_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    game.set_clock(Clock::new(
+        TimeControl::sudden_death(secs(60)),
+        Arc::new(time.clone()),
+    ));
+    time.advance(secs(61));
+    assert_eq!(game.try_play(&mate), Err(chesslib::IllegalMove::GameOver));
+    assert_eq!(game.status(), GameStatus::Timeout(Some(Color::Black)));
+}
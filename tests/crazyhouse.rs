@@ -0,0 +1,147 @@
+#![allow(clippy::needless_return)]
+
+use chesslib::end_state::GameStatus;
+use chesslib::move_generation::MoveGeneration;
+use chesslib::move_utils::Move;
+use chesslib::pgn::read_game;
+use chesslib::position::Position;
+use chesslib::square_utils::square_from_string;
+use chesslib::variant::{self, Crazyhouse, Variant};
+use chesslib::{Color, Game, IllegalMove, PieceKind};
+use std::sync::Arc;
+
+mod utils;
+
+use utils::play;
+
+#[test]
+fn fen_with_pockets_and_promoted_pieces() {
+    let fen = "r3k3/8/8/8/8/8/8/Q~3K3[QNPPp] w q - 0 30";
+    let position = Position::from_crazyhouse_fen(fen).unwrap();
+    assert_eq!(position.to_fen(), fen);
+    assert!(position.is_promoted(square_from_string("a1")));
+    assert!(!position.is_promoted(square_from_string("a8")));
+    assert_eq!(position.pocket(&Color::White, &PieceKind::Pawn), 2);
+    assert_eq!(position.pocket(&Color::Black, &PieceKind::Pawn), 1);
+    assert_eq!(position.pocket(&Color::Black, &PieceKind::Knight), 0);
+
+    let start = Crazyhouse.starting_position();
+    assert_eq!(
+        start.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+    );
+    assert!(Position::from_crazyhouse_fen("8/8/8/8/8/8/8/K6k[X] w - - 0 1").is_err());
+}
+
+#[test]
+fn captures_fill_the_pocket() {
+    let mut game = Game::with_variant(Arc::new(Crazyhouse));
+    play(&mut game, &["e4", "d5", "exd5"]);
+    assert_eq!(game.board.pocket(&Color::White, &PieceKind::Pawn), 1);
+    play(&mut game, &["Qxd5"]);
+    assert_eq!(game.board.pocket(&Color::Black, &PieceKind::Pawn), 1);
+
+    let drop = Move::drop_piece(PieceKind::Pawn, square_from_string("e6"));
+    let outcome = game.try_play(&drop).unwrap();
+    assert_eq!(outcome.played, drop);
+    assert_eq!(game.board.pocket(&Color::White, &PieceKind::Pawn), 0);
+    assert_eq!(
+        game.board.to_fen(),
+        "rnb1kbnr/ppp1pppp/4P3/3q4/8/8/PPPP1PPP/RNBQKBNR[p] b KQkq - 1 3"
+    );
+
+    game.undo();
+    assert_eq!(game.board.pocket(&Color::White, &PieceKind::Pawn), 1);
+    assert_eq!(
+        game.try_play(&Move::drop_piece(
+            PieceKind::Knight,
+            square_from_string("e6")
+        )),
+        Err(IllegalMove::InvalidMovement)
+    );
+}
+
+#[test]
+fn promoted_pieces_return_as_pawns() {
+    let fen = "1rk5/P7/8/8/8/8/8/4K3[] w - - 0 1";
+    let mut game = Game::try_variant_from_fen(Arc::new(Crazyhouse), fen).unwrap();
+    play(&mut game, &["axb8=Q+"]);
+    assert_eq!(game.board.to_fen(), "1Q~k5/8/8/8/8/8/8/4K3[R] b - - 0 1");
+    assert_eq!(game.board.pocket(&Color::White, &PieceKind::Rook), 1);
+    play(&mut game, &["Kxb8"]);
+    // the queen was a pawn before it promoted
+    assert_eq!(game.board.pocket(&Color::Black, &PieceKind::Pawn), 1);
+    assert_eq!(game.board.pocket(&Color::Black, &PieceKind::Queen), 0);
+}
+
+#[test]
+fn drop_notation() {
+    let position = Position::from_crazyhouse_fen("4k3/8/8/8/8/8/8/4K3[Np] w - - 0 1").unwrap();
+    let drop = Move::drop_piece(PieceKind::Knight, square_from_string("f6"));
+    assert_eq!(drop.to_long_algebraic(), "N@f6");
+    assert_eq!(Crazyhouse.move_to_san(&position, &drop), "N@f6+");
+    assert_eq!(
+        Crazyhouse.move_from_san(&position, "N@f6+"),
+        Some(drop.clone())
+    );
+    let board = chesslib::BoardState::from(&position);
+    assert_eq!(Move::try_from_long_algebraic("N@f6", &board).unwrap(), drop);
+    assert!(Move::try_from_long_algebraic("K@f6", &board).is_err());
+
+    // pawns are not dropped on the first and last rank, nor from the other pocket
+    let moves = Crazyhouse.legal_moves(&position);
+    assert_eq!(moves.iter().filter(|mov| mov.drop.is_some()).count(), 62);
+    assert_eq!(Crazyhouse.move_from_san(&position, "P@e4"), None);
+    // the rules of dropping are those of the variant, not of the position
+    assert!(position.legal_moves().iter().all(|mov| mov.drop.is_none()));
+}
+
+#[test]
+fn drops_block_checks_and_mate() {
+    let fen = "4k3/8/8/8/8/8/2PPP3/r2K4[R] w - - 0 1";
+    let position = Position::from_crazyhouse_fen(fen).unwrap();
+    let moves = Crazyhouse.legal_moves(&position);
+    assert!(moves.iter().all(|mov| mov.drop.is_some()));
+    assert_eq!(moves.len(), 2);
+
+    // the same position without the rook in the pocket is mate
+    let mut game =
+        Game::try_variant_from_fen(Arc::new(Crazyhouse), "4k3/8/8/8/8/8/2PPP3/r2K4[] w - - 0 1")
+            .unwrap();
+    assert_eq!(game.status(), GameStatus::Checkmate(Color::Black));
+    // bare kings draw only while the pockets are empty
+    game = Game::try_variant_from_fen(Arc::new(Crazyhouse), "4k3/8/8/8/8/8/8/4K3[p] w - - 0 1")
+        .unwrap();
+    assert_eq!(game.status(), GameStatus::Ongoing);
+}
+
+#[test]
+fn pgn_round_trip() {
+    let mut game = Game::with_variant(Arc::new(Crazyhouse));
+    play(&mut game, &["e4", "d5", "exd5", "Qxd5", "P@e6", "Bxe6"]);
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Variant \"Crazyhouse\"]"));
+    assert!(pgn.contains("3. P@e6 Bxe6"));
+    let read = read_game(&pgn).unwrap();
+    assert_eq!(read.game.board.to_fen(), game.board.to_fen());
+    assert_eq!(
+        variant::from_name("Crazyhouse").unwrap().name(),
+        "Crazyhouse"
+    );
+}
+
+#[test]
+fn promoted_pieces_change_the_hash() {
+    let promoted = Position::from_crazyhouse_fen("1Q~k5/8/8/8/8/8/8/4K3[R] b - - 0 1").unwrap();
+    let original = Position::from_crazyhouse_fen("1Qk5/8/8/8/8/8/8/4K3[R] b - - 0 1").unwrap();
+    assert_ne!(promoted.hash(), original.hash());
+
+    let mut position = Position::from_crazyhouse_fen("1rk5/P7/8/8/8/8/8/4K3[] w - - 0 1").unwrap();
+    let before = position.hash();
+    let promotion = Crazyhouse.move_from_san(&position, "axb8=Q+").unwrap();
+    let undo = Crazyhouse.make_move(&mut position, &promotion);
+    assert_eq!(position.hash(), promoted.hash());
+    Crazyhouse.unmake_move(&mut position, undo);
+    assert_eq!(position.hash(), before);
+    assert!(!position.is_promoted(square_from_string("b8")));
+}
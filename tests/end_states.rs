@@ -24,59 +24,303 @@ fn draw_by_insufficient_material() {
     assert!(position.is_draw() && position.insufficient_material());
 }
 
-#[test]
-fn draw_by_repetition() {
-    let mut game = Game::start_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+// castling rights are part of a position, so the knights shuffle back and
+// forth instead of the kings to reach the starting position again
+fn shuffle_knights(game: &mut Game) {
     game.execute_move(&Move::standard(
-        square_from_string("e2"),
-        square_from_string("e4"),
+        square_from_string("g1"),
+        square_from_string("f3"),
     ));
     game.execute_move(&Move::standard(
-        square_from_string("e7"),
-        square_from_string("e5"),
+        square_from_string("g8"),
+        square_from_string("f6"),
     ));
     game.execute_move(&Move::standard(
-        square_from_string("e1"),
-        square_from_string("e2"),
+        square_from_string("f3"),
+        square_from_string("g1"),
     ));
     game.execute_move(&Move::standard(
-        square_from_string("e8"),
-        square_from_string("e7"),
+        square_from_string("f6"),
+        square_from_string("g8"),
     ));
+}
+
+#[test]
+fn threefold_repetition_is_claimable() {
+    let mut game = Game::start_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    shuffle_knights(&mut game);
+    assert_eq!(game.repetition_count(), 2);
+    assert!(!game.can_claim_threefold());
+    shuffle_knights(&mut game);
+    assert_eq!(game.repetition_count(), 3);
+    assert!(
+        game.can_claim_threefold(),
+        "3-fold repetition can not be claimed: {:?}",
+        game.previous_positions,
+    );
+    assert_eq!(game.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+    assert!(!game.is_draw());
+}
+
+#[test]
+fn draw_by_fivefold_repetition() {
+    let mut game = Game::start_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    for _ in 0..3 {
+        shuffle_knights(&mut game);
+    }
+    assert!(!game.is_draw());
+    shuffle_knights(&mut game);
+    assert_eq!(game.repetition_count(), 5);
+    assert!(game.draw_by_repetition);
+    assert_eq!(game.draw_reason(), Some(DrawReason::FivefoldRepetition));
+}
+
+// after d7d5 the e5 pawn could capture en passant if it was not pinned, so
+// the position after the king shuffle is the same one
+#[test]
+fn impossible_en_passant_does_not_change_position() {
+    let mut game = Game::start_from_fen("4r1k1/3p4/8/4P3/8/8/8/4K3 b - - 0 1");
+    let moves = [("d7", "d5"), ("e1", "d1"), ("g8", "h8"), ("d1", "e1")];
+    for (start, end) in moves {
+        game.execute_move(&Move::standard(
+            square_from_string(start),
+            square_from_string(end),
+        ));
+    }
+    game.execute_move(&Move::standard(
+        square_from_string("h8"),
+        square_from_string("g8"),
+    ));
+    assert_eq!(game.repetition_count(), 2);
+}
+
+// the kings return to their squares but the castling rights are gone
+#[test]
+fn lost_castling_rights_change_position() {
+    let mut game = Game::start_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    for _ in 0..2 {
+        game.execute_move(&Move::standard(
+            square_from_string("e1"),
+            square_from_string("e2"),
+        ));
+        game.execute_move(&Move::standard(
+            square_from_string("e8"),
+            square_from_string("e7"),
+        ));
+        game.execute_move(&Move::standard(
+            square_from_string("e2"),
+            square_from_string("e1"),
+        ));
+        game.execute_move(&Move::standard(
+            square_from_string("e7"),
+            square_from_string("e8"),
+        ));
+    }
+    assert!(!game.draw_by_repetition);
+}
+
+#[test]
+fn game_hasnt_ended() {
+    let position = Game::start_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert!(!position.current_player_is_stalemate() && !position.current_player_is_checkmate());
+}
+
+#[test]
+fn late_move_number_is_not_a_draw() {
+    let game = Game::start_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 120");
+    assert!(!game.is_draw());
+}
+
+#[test]
+fn fifty_move_rule_is_claimable() {
+    let mut game = Game::start_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 99 80");
+    assert!(!game.can_claim_fifty_move_draw());
     game.execute_move(&Move::standard(
-        square_from_string("e2"),
         square_from_string("e1"),
+        square_from_string("d1"),
     ));
+    assert!(game.can_claim_fifty_move_draw());
+    assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+    assert_eq!(game.draw_reason(), None);
+    // a pawn move resets the clock
     game.execute_move(&Move::standard(
-        square_from_string("e7"),
         square_from_string("e8"),
+        square_from_string("d8"),
     ));
     game.execute_move(&Move::standard(
-        square_from_string("e1"),
         square_from_string("e2"),
+        square_from_string("e4"),
     ));
-    game.execute_move(&Move::standard(
-        square_from_string("e8"),
-        square_from_string("e7"),
-    ));
+    assert!(!game.can_claim_fifty_move_draw());
+}
+
+#[test]
+fn seventy_five_move_rule_is_automatic() {
+    let game = Game::start_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 150 100");
+    assert_eq!(game.draw_reason(), Some(DrawReason::SeventyFiveMoveRule));
+    assert!(game.is_draw());
+    // unless the last move was mate
+    let game = Game::start_from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 150 100");
+    assert_eq!(game.draw_reason(), None);
+    assert!(game.winner().is_some());
+}
+
+#[test]
+fn insufficient_material_combinations() {
+    let insufficient = [
+        "8/8/3k4/8/8/8/2K5/8 w - - 0 1",
+        "8/8/3k4/8/8/8/2KB4/8 w - - 0 1",
+        "8/8/3k4/8/8/8/2KN4/8 w - - 0 1",
+        "8/8/3k1b2/8/8/8/2KB4/8 w - - 0 1",
+        "b7/8/3k4/8/8/5B2/2K5/7B w - - 0 1",
+    ];
+    for fen in insufficient {
+        assert!(Game::start_from_fen(fen).insufficient_material(), "{}", fen);
+    }
+    let sufficient = [
+        "8/8/3k4/8/8/8/2KP4/8 w - - 0 1",
+        "8/8/3k4/8/8/8/2KR4/8 w - - 0 1",
+        "8/8/3kn3/8/8/8/2KN4/8 w - - 0 1",
+        "8/8/3k4/8/8/8/2KNB3/8 w - - 0 1",
+        "8/8/3kb3/8/8/8/2KB4/8 w - - 0 1",
+        "8/8/3k4/8/8/8/2KNN3/8 w - - 0 1",
+    ];
+    for fen in sufficient {
+        assert!(
+            !Game::start_from_fen(fen).insufficient_material(),
+            "{}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn dead_position_with_locked_pawns() {
+    let locked = Game::start_from_fen("8/4k3/8/1p1p1p1p/1P1P1P1P/8/4K3/8 w - - 0 1");
+    assert!(locked.is_dead_position());
+    assert!(!locked.is_draw());
+    // the white king can walk around the chain on the h-file
+    let open = Game::start_from_fen("8/4k3/8/1p1p1p2/1P1P1P2/8/4K3/8 w - - 0 1");
+    assert!(!open.is_dead_position());
+    // a pawn can capture
+    let capture = Game::start_from_fen("8/4k3/8/1p1p1p1p/1PPP1P1P/8/4K3/8 w - - 0 1");
+    assert!(!capture.is_dead_position());
+    assert!(!Game::new().is_dead_position());
+    assert!(Game::start_from_fen("8/8/3k4/8/8/8/2KN4/8 w - - 0 1").is_dead_position());
+}
+
+#[test]
+fn status_of_finished_games() {
+    let cases = [
+        (
+            "8/8/8/8/8/5KQk/8/8 b - - 0 1",
+            GameStatus::Checkmate(fen::Color::White),
+        ),
+        ("8/2k5/8/8/8/8/2q5/K7 w - - 0 1", GameStatus::Stalemate),
+        ("8/8/3k4/8/8/8/2KN4/8 w - - 0 1", GameStatus::Insufficient),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 150 90", GameStatus::FiftyMove),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", GameStatus::Ongoing),
+    ];
+    for (fen, status) in cases {
+        assert_eq!(Game::start_from_fen(fen).status(), status, "{}", fen);
+    }
+}
+
+#[test]
+fn status_maps_to_pgn_tags() {
+    use chesslib::pgn::GameResult;
+    let checkmate = GameStatus::Checkmate(fen::Color::Black);
+    assert_eq!(checkmate.result(), GameResult::BlackWins);
+    assert_eq!(checkmate.termination(), "normal");
+    assert_eq!(GameStatus::Repetition.result(), GameResult::Draw);
+    assert_eq!(GameStatus::Ongoing.result(), GameResult::Unknown);
+    assert_eq!(GameStatus::Ongoing.termination(), "unterminated");
+    let timeout = GameStatus::Timeout(Some(fen::Color::White));
+    assert_eq!(timeout.result(), GameResult::WhiteWins);
+    assert_eq!(timeout.termination(), "time forfeit");
+    assert_eq!(GameStatus::Timeout(None).result(), GameResult::Draw);
+}
+
+#[test]
+fn resignation() {
+    let mut game = Game::new();
+    game.resign(fen::Color::White);
+    assert_eq!(game.status(), GameStatus::Resignation(fen::Color::Black));
+    // the game is over, a later resignation changes nothing
+    game.resign(fen::Color::Black);
+    assert_eq!(game.status(), GameStatus::Resignation(fen::Color::Black));
+    assert!(game
+        .to_pgn()
+        .contains("[Result \"0-1\"]\n[Termination \"normal\"]\n"));
+    assert_eq!(
+        game.try_play(&Move::standard(
+            square_from_string("e2"),
+            square_from_string("e4"),
+        )),
+        Err(chesslib::IllegalMove::GameOver)
+    );
+}
+
+#[test]
+fn draw_offers() {
+    let mut game = Game::new();
+    assert!(!game.accept_draw());
+    game.offer_draw(fen::Color::White);
+    game.decline_draw();
+    assert!(!game.accept_draw());
+
+    // the offer stands while the player who made it moves
+    game.offer_draw(fen::Color::White);
     game.execute_move(&Move::standard(
         square_from_string("e2"),
-        square_from_string("e1"),
+        square_from_string("e4"),
     ));
+    assert_eq!(game.pending_draw_offer(), Some(fen::Color::White));
+    // and expires when the opponent moves
     game.execute_move(&Move::standard(
         square_from_string("e7"),
-        square_from_string("e8"),
+        square_from_string("e5"),
     ));
-    assert!(
-        game.draw_by_repetition,
-        "3-fold repetition did not cause a draw: {:?}",
-        game.previous_positions,
-    );
-    assert!(game.is_draw());
+    assert_eq!(game.pending_draw_offer(), None);
+
+    game.offer_draw(fen::Color::White);
+    assert!(game.accept_draw());
+    assert_eq!(game.status(), GameStatus::Agreement);
+    assert_eq!(game.result(), chesslib::pgn::GameResult::Draw);
 }
 
 #[test]
-fn game_hasnt_ended() {
-    let position = Game::start_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-    assert!(!position.current_player_is_stalemate() && !position.current_player_is_checkmate());
+fn draw_offer_after_mate() {
+    let mut game = Game::start_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    game.offer_draw(fen::Color::Black);
+    game.execute_move(&Move::standard(
+        square_from_string("a1"),
+        square_from_string("a8"),
+    ));
+    assert_eq!(game.status(), GameStatus::Checkmate(fen::Color::White));
+    assert_eq!(game.pending_draw_offer(), None);
+    game.offer_draw(fen::Color::Black);
+    assert!(!game.accept_draw());
+    assert_eq!(game.status(), GameStatus::Checkmate(fen::Color::White));
+}
+
+#[test]
+fn undo_takes_back_resignation() {
+    let mut game = Game::new();
+    game.execute_move(&Move::standard(
+        square_from_string("e2"),
+        square_from_string("e4"),
+    ));
+    game.offer_draw(fen::Color::White);
+    game.resign(fen::Color::Black);
+    assert_eq!(game.status(), GameStatus::Resignation(fen::Color::White));
+    game.undo();
+    assert_eq!(game.status(), GameStatus::Ongoing);
+    assert_eq!(game.pending_draw_offer(), None);
+    assert!(game
+        .try_play(&Move::standard(
+            square_from_string("d2"),
+            square_from_string("d4"),
+        ))
+        .is_ok());
 }
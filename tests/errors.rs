@@ -0,0 +1,114 @@
+use chesslib::move_utils::Move;
+use chesslib::square_utils::try_square_from_string;
+use chesslib::{Error, Game, PieceKind};
+
+#[test]
+fn invalid_squares() {
+    assert_eq!(try_square_from_string("h8"), Ok(63));
+    for square in ["", "e", "i1", "a0", "a9", "A1", "e44", "1e"] {
+        assert_eq!(
+            try_square_from_string(square),
+            Err(Error::InvalidSquare(square.to_string()))
+        );
+    }
+}
+
+#[test]
+fn malformed_long_algebraic() {
+    let board = fen::BoardState::from_fen("8/4P3/8/8/8/8/8/k3K3 w - - 0 1").unwrap();
+    let promotion = Move::try_from_long_algebraic("e7e8n", &board).unwrap();
+    assert_eq!(promotion.promotion, Some(PieceKind::Knight));
+    for mov in ["", "e7", "e7e", "e7e8kk", "e7e8k", "z7e8", "e7e9"] {
+        assert_eq!(
+            Move::try_from_long_algebraic(mov, &board),
+            Err(Error::MalformedMove(mov.to_string()))
+        );
+    }
+}
+
+#[test]
+fn illegal_san() {
+    let board = fen::BoardState::from_fen(chesslib::STARTING_POSITION).unwrap();
+    assert!(Move::try_from_san("Nf3", &board).is_ok());
+    assert_eq!(
+        Move::try_from_san("Ke2", &board),
+        Err(Error::IllegalMove("Ke2".to_string()))
+    );
+}
+
+#[test]
+fn invalid_fen() {
+    assert!(Game::try_from_fen(chesslib::STARTING_POSITION).is_ok());
+    assert!(matches!(
+        Game::try_from_fen("not a fen"),
+        Err(Error::InvalidFen(_))
+    ));
+}
+
+#[test]
+fn fen_placement_is_checked() {
+    for fen in [
+        "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "8/8/8/8/8/8/8/8 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3/8 w - - 0 1",
+    ] {
+        assert_eq!(
+            Game::try_from_fen(fen).err(),
+            Some(Error::InvalidFen(fen.to_string()))
+        );
+    }
+    let crazyhouse = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR~~~~[] w KQkq - 0 1";
+    assert!(chesslib::position::Position::from_crazyhouse_fen(crazyhouse).is_err());
+}
+
+#[test]
+fn out_of_range_squares() {
+    let mut game = Game::new();
+    assert_eq!(
+        game.try_play(&Move::standard(64, 12)),
+        Err(chesslib::IllegalMove::InvalidMovement)
+    );
+    assert_eq!(
+        game.try_play(&Move::standard(12, 100)),
+        Err(chesslib::IllegalMove::InvalidMovement)
+    );
+}
+
+#[test]
+fn fen_fields_are_checked() {
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w - é 0 1",
+        "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w KX - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - e4 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - a 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 -1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 0",
+        "4k3/8/8/8/8/8/8/4K3  w - - 0 1",
+    ] {
+        assert_eq!(
+            Game::try_from_fen(fen).err(),
+            Some(Error::InvalidFen(fen.to_string()))
+        );
+    }
+}
+
+// the en passant square has to be the one a pawn of the opponent skipped
+#[test]
+fn impossible_en_passant_square() {
+    for fen in [
+        "4k3/8/8/8/8/8/3PP3/4K3 w - e3 0 1",
+        "4k3/8/4p3/4P3/8/8/8/4K3 w - e6 0 1",
+        "4k3/8/8/8/4p3/8/8/4K3 w - e6 0 1",
+        "4k3/8/8/8/8/8/8/4K3 b - e3 0 1",
+    ] {
+        assert_eq!(
+            Game::try_from_fen(fen).err(),
+            Some(Error::InvalidFen(fen.to_string()))
+        );
+    }
+    assert!(Game::try_from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1").is_ok());
+    assert!(Game::try_from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").is_ok());
+}
@@ -0,0 +1,78 @@
+use chesslib::end_state::StateCheck;
+use chesslib::move_utils::Move;
+use chesslib::square_utils::square_from_string;
+use chesslib::{Game, STARTING_POSITION};
+
+mod utils;
+
+use utils::play;
+
+#[test]
+fn undo_and_redo() {
+    let mut game = Game::new();
+    play(&mut game, &["e4", "d5", "exd5"]);
+    let after_capture = game.board.clone();
+    assert_eq!(game.moves().len(), 3);
+
+    let taken_back = game.undo().unwrap();
+    assert_eq!(
+        taken_back,
+        Move::standard(square_from_string("e4"), square_from_string("d5"))
+    );
+    assert_eq!(
+        game.board.to_fen(),
+        "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"
+    );
+    game.undo();
+    game.undo();
+    assert_eq!(game.board.to_fen(), STARTING_POSITION);
+    assert_eq!(game.undo(), None);
+
+    for _ in 0..3 {
+        game.redo().unwrap();
+    }
+    assert_eq!(game.board, after_capture);
+    assert_eq!(game.redo(), None);
+}
+
+#[test]
+fn new_move_clears_redo() {
+    let mut game = Game::new();
+    play(&mut game, &["e4", "e5"]);
+    game.undo();
+    play(&mut game, &["c5"]);
+    assert_eq!(game.redo(), None);
+    assert_eq!(game.moves().len(), 2);
+}
+
+#[test]
+fn undo_rolls_back_repetition() {
+    let mut game = Game::new();
+    let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+    for _ in 0..4 {
+        play(&mut game, &shuffle);
+    }
+    assert!(game.draw_by_repetition && game.is_draw());
+
+    game.undo();
+    assert!(!game.draw_by_repetition && !game.is_draw());
+    assert_eq!(game.previous_positions.values().sum::<usize>(), 16);
+    game.redo();
+    assert!(game.draw_by_repetition);
+}
+
+#[test]
+fn position_at_ply() {
+    let mut game = Game::start_from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    play(&mut game, &["e4", "Kd7", "e5"]);
+    assert_eq!(
+        game.position_at(0).unwrap().to_fen(),
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"
+    );
+    assert_eq!(
+        game.position_at(2).unwrap().to_fen(),
+        "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2"
+    );
+    assert_eq!(game.position_at(3).unwrap(), game.board);
+    assert_eq!(game.position_at(4), None);
+}
@@ -15,6 +15,7 @@ fn white_can_castle_queen_and_kingside() {
             promotion: None,
             is_en_passant: false,
             castles: true,
+            drop: None,
         },
         Move {
             start_square: square_from_string("e1"),
@@ -22,6 +23,7 @@ fn white_can_castle_queen_and_kingside() {
             promotion: None,
             is_en_passant: false,
             castles: true,
+            drop: None,
         },
     ];
     assert!(
@@ -42,6 +44,7 @@ fn black_can_castle_queen_and_kingside() {
             promotion: None,
             is_en_passant: false,
             castles: true,
+            drop: None,
         },
         Move {
             start_square: square_from_string("e8"),
@@ -49,6 +52,7 @@ fn black_can_castle_queen_and_kingside() {
             promotion: None,
             is_en_passant: false,
             castles: true,
+            drop: None,
         },
     ];
     assert!(
@@ -69,6 +73,7 @@ fn white_cant_castle_through_check() {
         promotion: None,
         is_en_passant: false,
         castles: true,
+        drop: None,
     }];
     let wanted_move: Vec<Move> = vec![Move {
         start_square: square_from_string("e1"),
@@ -76,6 +81,7 @@ fn white_cant_castle_through_check() {
         promotion: None,
         is_en_passant: false,
         castles: true,
+        drop: None,
     }];
     assert!(
         !utils::contains_moves(&legal_moves, &unwanted_move)
@@ -97,6 +103,7 @@ fn white_cant_castle_through_check_2() {
             promotion: None,
             is_en_passant: false,
             castles: true,
+            drop: None,
         },
         Move {
             start_square: square_from_string("e1"),
@@ -104,6 +111,7 @@ fn white_cant_castle_through_check_2() {
             promotion: None,
             is_en_passant: false,
             castles: true,
+            drop: None,
         },
     ];
     assert!(
@@ -113,4 +121,121 @@ fn white_cant_castle_through_check_2() {
     );
 }
 
+// each castling right only allows castling to its own side
+#[test]
+fn castling_rights_are_independent() {
+    let kingside_only = fen::BoardState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1").unwrap();
+    let queenside_only = fen::BoardState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1").unwrap();
+    let short_castle: Vec<Move> = vec![Move {
+        start_square: square_from_string("e1"),
+        end_square: square_from_string("g1"),
+        promotion: None,
+        is_en_passant: false,
+        castles: true,
+        drop: None,
+    }];
+    let long_castle: Vec<Move> = vec![Move {
+        start_square: square_from_string("e1"),
+        end_square: square_from_string("c1"),
+        promotion: None,
+        is_en_passant: false,
+        castles: true,
+        drop: None,
+    }];
+    let legal_moves = kingside_only.legal_moves();
+    assert!(
+        utils::contains_moves(&legal_moves, &short_castle)
+            && !utils::contains_moves(&legal_moves, &long_castle),
+        "{:?}",
+        legal_moves
+    );
+    let legal_moves = queenside_only.legal_moves();
+    assert!(
+        !utils::contains_moves(&legal_moves, &short_castle)
+            && utils::contains_moves(&legal_moves, &long_castle),
+        "{:?}",
+        legal_moves
+    );
+}
+
+// the knight on b1 blocks the long castle even though the king never crosses b1
+#[test]
+fn long_castle_blocked_on_b_file() {
+    let position = fen::BoardState::from_fen("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1").unwrap();
+    let legal_moves = position.legal_moves();
+    let unwanted_move: Vec<Move> = vec![Move {
+        start_square: square_from_string("e1"),
+        end_square: square_from_string("c1"),
+        promotion: None,
+        is_en_passant: false,
+        castles: true,
+        drop: None,
+    }];
+    assert!(
+        !utils::contains_moves(&legal_moves, &unwanted_move),
+        "{:?}",
+        legal_moves
+    );
+}
+
+// the king would land on the knight on g8
+#[test]
+fn black_short_castle_blocked_on_landing_square() {
+    let position = fen::BoardState::from_fen("r3k1nr/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+    let legal_moves = position.legal_moves();
+    let unwanted_move: Vec<Move> = vec![Move {
+        start_square: square_from_string("e8"),
+        end_square: square_from_string("g8"),
+        promotion: None,
+        is_en_passant: false,
+        castles: true,
+        drop: None,
+    }];
+    assert!(
+        !utils::contains_moves(&legal_moves, &unwanted_move),
+        "{:?}",
+        legal_moves
+    );
+}
+
+// the castling right is still set but the rook is gone from a1
+#[test]
+fn cant_castle_without_rook() {
+    let position = fen::BoardState::from_fen("r3k2r/8/8/8/8/8/8/4K2R w KQkq - 0 1").unwrap();
+    let legal_moves = position.legal_moves();
+    let unwanted_move: Vec<Move> = vec![Move {
+        start_square: square_from_string("e1"),
+        end_square: square_from_string("c1"),
+        promotion: None,
+        is_en_passant: false,
+        castles: true,
+        drop: None,
+    }];
+    assert!(
+        !utils::contains_moves(&legal_moves, &unwanted_move),
+        "{:?}",
+        legal_moves
+    );
+}
+
+// only a rook in the corner allows castling
+#[test]
+fn cant_castle_with_knight_in_corner() {
+    let position = fen::BoardState::from_fen("r3k2n/8/8/8/8/8/8/R3K3 b kq - 0 1").unwrap();
+    let legal_moves = position.legal_moves();
+    let unwanted_move: Vec<Move> = vec![Move {
+        start_square: square_from_string("e8"),
+        end_square: square_from_string("g8"),
+        promotion: None,
+        is_en_passant: false,
+        castles: true,
+        drop: None,
+    }];
+    assert!(
+        !utils::contains_moves(&legal_moves, &unwanted_move),
+        "{:?}",
+        legal_moves
+    );
+}
+
 // black can only castle short, the long castle would go through a check
@@ -91,6 +91,7 @@ fn promotion() {
             promotion: Some(fen::PieceKind::Rook),
             is_en_passant: false,
             castles: false,
+            drop: None,
         },
         Move {
             start_square: square_from_string("g7"),
@@ -98,6 +99,7 @@ fn promotion() {
             promotion: Some(fen::PieceKind::Bishop),
             is_en_passant: false,
             castles: false,
+            drop: None,
         },
         Move {
             start_square: square_from_string("g7"),
@@ -105,6 +107,7 @@ fn promotion() {
             promotion: Some(fen::PieceKind::Knight),
             is_en_passant: false,
             castles: false,
+            drop: None,
         },
         Move {
             start_square: square_from_string("g7"),
@@ -112,6 +115,7 @@ fn promotion() {
             promotion: Some(fen::PieceKind::Queen),
             is_en_passant: false,
             castles: false,
+            drop: None,
         },
     ];
     assert!(utils::equal_moves(&legal_moves, &expected_moves));
@@ -145,6 +149,7 @@ fn en_passant() {
         promotion: None,
         is_en_passant: true,
         castles: false,
+        drop: None,
     }];
     assert!(
         utils::contains_moves(&legal_moves, &expected_moves),
@@ -152,3 +157,76 @@ fn en_passant() {
         legal_moves
     );
 }
+
+#[test]
+fn en_passant_removes_captured_pawn() {
+    let mut position = fen::BoardState::from_fen("1k6/4p3/8/5P2/8/8/8/1K6 b - - 0 1").unwrap();
+    Move::from_long_algebraic("e7e5".to_string(), &position).execute(&mut position);
+    let mov = Move::from_long_algebraic("f5e6".to_string(), &position);
+    assert!(mov.is_en_passant);
+    assert!(
+        position.legal_moves().contains(&mov),
+        "{:?}",
+        position.legal_moves()
+    );
+    mov.execute(&mut position);
+    assert_eq!(position.to_fen(), "1k6/8/4P3/8/8/8/8/1K6 b - - 0 2");
+}
+
+#[test]
+// capturing en passant would remove both pawns from the fifth rank
+// and expose the white king to the rook
+fn en_passant_horizontal_pin() {
+    let position = fen::BoardState::from_fen("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1").unwrap();
+    let legal_moves = position.legal_moves();
+    let illegal_move: Vec<Move> = vec![Move {
+        start_square: square_from_string("e5"),
+        end_square: square_from_string("d6"),
+        promotion: None,
+        is_en_passant: true,
+        castles: false,
+        drop: None,
+    }];
+    assert!(
+        !utils::contains_moves(&legal_moves, &illegal_move),
+        "{:?}",
+        legal_moves
+    );
+}
+
+#[test]
+fn en_passant_only_right_after_double_step() {
+    let mut position = fen::BoardState::from_fen("1k6/4p3/8/5P2/8/8/8/1K6 b - - 0 1").unwrap();
+    Move::from_long_algebraic("e7e5".to_string(), &position).execute(&mut position);
+    Move::from_long_algebraic("b1a1".to_string(), &position).execute(&mut position);
+    Move::from_long_algebraic("b8a8".to_string(), &position).execute(&mut position);
+    let legal_moves = position.legal_moves();
+    assert!(
+        !legal_moves.iter().any(|m| m.is_en_passant),
+        "{:?}",
+        legal_moves
+    );
+}
+
+#[test]
+// a bad en passant square next to the own pawn must not capture it
+fn en_passant_only_captures_enemy_pawns() {
+    let mut position =
+        chesslib::position::Position::from_fen("4k3/8/8/8/8/8/3PP3/4K3 w - e3 0 1").unwrap();
+    let legal_moves = position.legal_moves();
+    assert!(
+        !legal_moves.iter().any(|m| m.is_en_passant),
+        "{:?}",
+        legal_moves
+    );
+    let mov = Move {
+        start_square: square_from_string("d2"),
+        end_square: square_from_string("e3"),
+        promotion: None,
+        is_en_passant: true,
+        castles: false,
+        drop: None,
+    };
+    position.make_move(&mov);
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/4P3/4P3/4K3 b - - 0 1");
+}
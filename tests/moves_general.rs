@@ -1,5 +1,6 @@
 use chesslib::move_generation::MoveGeneration;
 use chesslib::move_utils::*;
+use chesslib::position::Position;
 use chesslib::square_utils::square_from_string;
 
 mod utils;
@@ -31,13 +32,14 @@ fn kingside_castle() {
         start_square: square_from_string("e1"),
         end_square: square_from_string("g1"),
         castles: true,
+        drop: None,
         promotion: None,
         is_en_passant: false,
     };
     mov.execute(&mut position);
     let end_pos_fen = position.to_fen();
     assert!(
-        end_pos_fen == "2k5/8/8/8/8/8/8/5RK1 b - - 0 1",
+        end_pos_fen == "2k5/8/8/8/8/8/8/5RK1 b - - 1 1",
         "{}",
         end_pos_fen
     );
@@ -50,13 +52,14 @@ fn queenside_castle() {
         start_square: square_from_string("e8"),
         end_square: square_from_string("c8"),
         castles: true,
+        drop: None,
         promotion: None,
         is_en_passant: false,
     };
     mov.execute(&mut position);
     let end_pos_fen = position.to_fen();
     assert!(
-        end_pos_fen == "2kr4/8/8/8/8/8/8/5K2 w - - 0 1",
+        end_pos_fen == "2kr4/8/8/8/8/8/8/5K2 w - - 1 2",
         "{}",
         end_pos_fen
     );
@@ -81,6 +84,48 @@ fn king_move_ends_castling() {
     );
 }
 
+#[test]
+fn double_push_sets_en_passant_square() {
+    let mut position =
+        fen::BoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap();
+    Move::from_long_algebraic("e2e4".to_string(), &position).execute(&mut position);
+    assert_eq!(
+        position.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+    Move::from_long_algebraic("g8f6".to_string(), &position).execute(&mut position);
+    assert_eq!(
+        position.to_fen(),
+        "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"
+    );
+}
+
+#[test]
+fn halfmove_clock_resets_on_capture() {
+    let mut position = fen::BoardState::from_fen("4k3/8/8/3p4/8/8/8/3RK3 w - - 12 40").unwrap();
+    Move::from_long_algebraic("e1f2".to_string(), &position).execute(&mut position);
+    assert_eq!(position.to_fen(), "4k3/8/8/3p4/8/8/5K2/3R4 b - - 13 40");
+    Move::from_long_algebraic("e8e7".to_string(), &position).execute(&mut position);
+    Move::from_long_algebraic("d1d5".to_string(), &position).execute(&mut position);
+    assert_eq!(position.to_fen(), "8/4k3/8/3R4/8/8/5K2/8 b - - 0 41");
+}
+
+#[test]
+fn black_rook_move_ends_black_castling() {
+    let mut position = fen::BoardState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+    Move::from_long_algebraic("h8h5".to_string(), &position).execute(&mut position);
+    assert!(position.white_can_oo && position.white_can_ooo);
+    assert!(!position.black_can_oo && position.black_can_ooo);
+}
+
+#[test]
+fn captured_rook_ends_castling() {
+    let mut position = fen::BoardState::from_fen("r3k2r/8/8/8/8/8/6b1/R3K2R b KQkq - 0 1").unwrap();
+    Move::from_long_algebraic("g2h1".to_string(), &position).execute(&mut position);
+    assert_eq!(position.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2b w Qkq - 0 2");
+}
+
 #[test]
 fn regular_move_to_string() {
     let position =
@@ -98,3 +143,48 @@ fn promotion_to_string() {
     let mov = Move::from_long_algebraic(expected.to_string(), &position);
     assert!(expected == mov.to_long_algebraic());
 }
+
+#[test]
+fn unmake_restores_position() {
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 3 17",
+        "1k6/6P1/8/8/8/8/1p6/1K6 w - - 0 1",
+    ] {
+        let mut position = Position::from_fen(fen).unwrap();
+        for mov in position.legal_moves() {
+            let undo = position.make_move(&mov);
+            position.unmake_move(undo);
+            assert_eq!(position.to_fen(), fen, "{}", mov);
+        }
+    }
+}
+
+#[test]
+fn unmake_restores_captured_piece() {
+    let mut position = Position::from_fen("8/8/1k6/1b6/8/3P4/4K3/8 b - - 0 1").unwrap();
+    let mov = Move::standard(square_from_string("b5"), square_from_string("e2"));
+    let undo = position.make_move(&mov);
+    assert!(position.king_square(&fen::Color::White).is_none());
+    position.unmake_move(undo);
+    assert_eq!(position.to_fen(), "8/8/1k6/1b6/8/3P4/4K3/8 b - - 0 1");
+}
+
+#[test]
+fn straight_line_moves() {
+    let board = fen::BoardState::from_fen("4k3/8/8/1p6/8/3B4/8/4K3 w - - 0 1").unwrap();
+    let bishop = square_from_string("d3");
+    let diagonals = vec![(1, 1), (1, -1), (-1, 1), (-1, -1)];
+    let moves = board.straight_line_moves(bishop, diagonals.clone(), true, None);
+    assert!(utils::equal_moves(
+        &moves,
+        &board.generate_bishop_moves(bishop)
+    ));
+    // the pawn on b5 can not be captured, g6 is too far away
+    let short = board.straight_line_moves(bishop, diagonals, false, Some(2));
+    let expected: Vec<Move> = ["e4", "f5", "c4", "e2", "f1", "c2", "b1"]
+        .iter()
+        .map(|target| Move::standard(bishop, square_from_string(target)))
+        .collect();
+    assert!(utils::equal_moves(&short, &expected));
+}
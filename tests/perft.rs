@@ -0,0 +1,161 @@
+#![allow(clippy::needless_return)]
+
+use chesslib::position::Position;
+use chesslib::variant::{Atomic, Crazyhouse, Variant};
+
+// The deepest depth checked for each position, the known node counts go
+// deeper than this. Set CHESSLIB_PERFT_DEPTH to check more of them.
+const DEFAULT_DEPTH: usize = 3;
+
+fn max_depth() -> usize {
+    return std::env::var("CHESSLIB_PERFT_DEPTH")
+        .ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(DEFAULT_DEPTH);
+}
+
+// node counts of depth 1, 2, ... as published on the chess programming wiki
+fn check_perft(fen: &str, expected: &[u64]) {
+    let position = Position::from_fen(fen).unwrap();
+    for (depth, nodes) in expected.iter().enumerate().take(max_depth()) {
+        assert_eq!(
+            position.perft(depth + 1),
+            *nodes,
+            "perft({}) of {}",
+            depth + 1,
+            fen
+        );
+    }
+}
+
+#[test]
+fn initial_position() {
+    check_perft(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[20, 400, 8902, 197281, 4865609, 119060324],
+    );
+}
+
+#[test]
+fn kiwipete() {
+    check_perft(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97862, 4085603, 193690690],
+    );
+}
+
+#[test]
+fn position_3() {
+    check_perft(
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2812, 43238, 674624, 11030083],
+    );
+}
+
+#[test]
+fn position_4() {
+    check_perft(
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9467, 422333, 15833292],
+    );
+}
+
+#[test]
+fn position_4_mirrored() {
+    check_perft(
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        &[6, 264, 9467, 422333, 15833292],
+    );
+}
+
+#[test]
+fn position_5() {
+    check_perft(
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1486, 62379, 2103487, 89941194],
+    );
+}
+
+#[test]
+fn position_6() {
+    check_perft(
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2079, 89890, 3894594, 164075551],
+    );
+}
+
+// Chess960 positions with castling rights given in Shredder-FEN
+#[test]
+fn chess960_positions() {
+    check_perft(
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        &[21, 528, 12189, 326672, 8146062],
+    );
+    check_perft(
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        &[20, 479, 10471, 273318, 6417013],
+    );
+}
+
+// Drops first appear at depth 5, the first capture being made at depth 3
+#[test]
+fn crazyhouse_initial_position() {
+    let position =
+        Position::from_crazyhouse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1")
+            .unwrap();
+    let expected = [20, 400, 8902, 197281, 4888832];
+    for (depth, nodes) in expected.iter().enumerate().take(max_depth()) {
+        assert_eq!(
+            Crazyhouse.perft(&position, depth + 1),
+            *nodes,
+            "perft({})",
+            depth + 1
+        );
+    }
+}
+
+// Atomic chess, where captures explode and kings can not capture
+#[test]
+fn atomic_positions() {
+    let positions: [(&str, [u64; 4]); 3] = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            [20, 400, 8902, 197326],
+        ),
+        (
+            "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+            [40, 1238, 45237, 1434825],
+        ),
+        (
+            "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+            [28, 833, 23353, 714499],
+        ),
+    ];
+    for (fen, expected) in positions {
+        let position = Atomic.position_from_fen(fen).unwrap();
+        for (depth, nodes) in expected.iter().enumerate().take(max_depth()) {
+            assert_eq!(
+                Atomic.perft(&position, depth + 1),
+                *nodes,
+                "perft({}) of {}",
+                depth + 1,
+                fen
+            );
+        }
+    }
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let position =
+        Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let divide = position.divide(2);
+    assert_eq!(divide.len(), 48);
+    assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+    let moves: Vec<String> = divide
+        .iter()
+        .map(|(mov, _)| mov.to_long_algebraic())
+        .collect();
+    assert!(moves.windows(2).all(|pair| pair[0] < pair[1]));
+}
@@ -0,0 +1,145 @@
+use chesslib::end_state::StateCheck;
+use chesslib::pgn::*;
+use std::io::{BufReader, Cursor};
+
+const IMMORTAL_GAME: &str = r#"[Event "London casual game"]
+[Site "London ENG"]
+[Date "1851.06.21"]
+[Round "-"]
+[White "Adolf Anderssen"]
+[Black "Lionel Kieseritzky"]
+[Result "1-0"]
+[Annotator "\"Someone\""]
+
+1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ 4. Kf1 b5?! 5. Bxb5 Nf6 6. Nf3 Qh6 7. d3 Nh5
+8. Nh4 Qg5 9. Nf5 c6 10. g4 Nf6 11. Rg1 cxb5 12. h4 Qg6 13. h5 Qg5 14. Qf3 Ng8
+15. Bxf4 Qf6 16. Nc3 Bc5 17. Nd5 Qxb2 18. Bd6 Bxg1 {18...Qxa1+ was better} 19.
+e5 Qxa1+ 20. Ke2 Na6 21. Nxg7+ Kd8 22. Qf6+ Nxf6 23. Be7# 1-0
+"#;
+
+#[test]
+fn tags_and_moves() {
+    let game = read_game(IMMORTAL_GAME).unwrap();
+    assert_eq!(game.tags.len(), 8);
+    assert_eq!(game.tag("White"), Some("Adolf Anderssen"));
+    assert_eq!(game.tag("Annotator"), Some("\"Someone\""));
+    assert_eq!(game.moves.len(), 45);
+    assert_eq!(game.result, GameResult::WhiteWins);
+    assert!(game.game.current_player_is_checkmate());
+}
+
+#[test]
+fn comments_nags_and_variations() {
+    let pgn = "1. e4 $1 {best by test} e5 (1... c5 2. Nf3 (2. c3 d5) d6; Sicilian\n) \
+               2. Nf3 Nc6 ; main line\n3. Bb5 $2 a6 *";
+    let game = read_game(pgn).unwrap();
+    assert_eq!(game.moves.len(), 6);
+    assert_eq!(game.result, GameResult::Unknown);
+    assert_eq!(
+        game.game.board.to_fen(),
+        "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4"
+    );
+}
+
+#[test]
+fn setup_from_fen_tag() {
+    let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 w Q - 0 1\"]\n\n1. O-O-O Kf7 1/2-1/2";
+    let game = read_game(pgn).unwrap();
+    assert_eq!(game.result, GameResult::Draw);
+    assert_eq!(game.game.board.to_fen(), "8/5k2/8/8/8/8/8/2KR4 w - - 2 2");
+}
+
+#[test]
+fn illegal_move() {
+    let err = read_game("1. e4 e5 2. Ke3 *").err().unwrap();
+    assert!(
+        matches!(err, PgnError::IllegalMove { ply: 3, ref san } if san == "Ke3"),
+        "{}",
+        err
+    );
+    assert!(matches!(
+        read_game("1. e4 (1. d4 *"),
+        Err(PgnError::UnbalancedVariation)
+    ));
+}
+
+#[test]
+fn reads_multiple_games() {
+    let pgn = format!(
+        "{}\n[Event \"?\"]\n\n1. d4 d5 {{ a comment\n[with a bracket] }} 0-1\n\n1. c4 *\n",
+        IMMORTAL_GAME
+    );
+    let reader = PgnReader::new(BufReader::new(Cursor::new(pgn)));
+    let games: Vec<PgnGame> = reader.map(|game| game.unwrap()).collect();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].moves.len(), 45);
+    assert_eq!(games[1].tag("Event"), Some("?"));
+    assert_eq!(games[1].moves.len(), 2);
+    assert_eq!(games[1].result, GameResult::BlackWins);
+}
+
+#[test]
+fn braces_in_tag_values() {
+    let pgn = "[Annotator \"foo {\"]\n\n1. e4 *\n\n[Event \"second\"]\n\n1. d4 *\n";
+    let reader = PgnReader::new(BufReader::new(Cursor::new(pgn)));
+    let games: Vec<PgnGame> = reader.map(|game| game.unwrap()).collect();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].tag("Annotator"), Some("foo {"));
+    assert_eq!(games[1].tag("Event"), Some("second"));
+}
+
+#[test]
+fn escaped_lines() {
+    let pgn = "% exported by some tool\n[Event \"?\"]\n\n1. e4 e5\n%2. d4 {\n2. Nf3 *\n";
+    let game = read_game(pgn).unwrap();
+    assert_eq!(game.moves.len(), 3);
+    let reader = PgnReader::new(BufReader::new(Cursor::new(pgn)));
+    assert_eq!(
+        reader
+            .map(|game| game.unwrap().moves.len())
+            .collect::<Vec<_>>(),
+        [3]
+    );
+}
+
+#[test]
+fn export_short_game() {
+    let mut game = chesslib::Game::new();
+    game.set_tag("White", "Player One");
+    game.set_tag("Opening", "King's Pawn");
+    for san in ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"] {
+        let mov = game.board.move_from_san(san).unwrap();
+        game.execute_move(&mov);
+    }
+    assert_eq!(
+        game.to_pgn(),
+        "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+         [White \"Player One\"]\n[Black \"?\"]\n[Result \"1-0\"]\n[Termination \"normal\"]\n[Opening \"King's Pawn\"]\n\n\
+         1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n"
+    );
+}
+
+#[test]
+fn export_round_trip() {
+    let game = read_game(IMMORTAL_GAME).unwrap();
+    let pgn = game.game.to_pgn();
+    assert!(pgn.lines().all(|line| line.len() <= 80));
+    assert!(pgn.contains("[Annotator \"\\\"Someone\\\"\"]"));
+    let reread = read_game(&pgn).unwrap();
+    assert_eq!(reread.tags[..7], game.tags[..7]);
+    assert_eq!(reread.tag("Termination"), Some("normal"));
+    assert_eq!(reread.tag("Annotator"), game.tag("Annotator"));
+    assert_eq!(reread.moves, game.moves);
+    assert_eq!(reread.result, GameResult::WhiteWins);
+}
+
+#[test]
+fn export_setup_position() {
+    let mut game = chesslib::Game::start_from_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 0 7");
+    let mov = game.board.move_from_san("Kd7").unwrap();
+    game.execute_move(&mov);
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 7\"]\n"));
+    assert!(pgn.ends_with("\n7... Kd7 *\n"));
+    assert!(!chesslib::Game::new().to_pgn().contains("SetUp"));
+}
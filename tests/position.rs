@@ -0,0 +1,97 @@
+use chesslib::move_generation::MoveGeneration;
+use chesslib::move_utils::Move;
+use chesslib::position::Position;
+use chesslib::square_utils::square_from_string;
+use fen::{Color, Piece, PieceKind};
+
+mod utils;
+
+#[test]
+fn fen_round_trip() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b Kq e3 12 34";
+    let board = fen::BoardState::from_fen(fen).unwrap();
+    let position = Position::from(&board);
+    assert_eq!(fen::BoardState::from(&position), board);
+    assert_eq!(position.to_fen(), fen);
+}
+
+#[test]
+fn piece_lookup() {
+    let position =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    assert_eq!(
+        position.piece_at(square_from_string("d8")),
+        Some(Piece {
+            kind: PieceKind::Queen,
+            color: Color::Black
+        })
+    );
+    assert_eq!(position.piece_at(square_from_string("e4")), None);
+    assert_eq!(
+        position.king_square(&Color::White),
+        Some(square_from_string("e1"))
+    );
+    assert_eq!(position.pieces(&Color::White, &PieceKind::Pawn), 0xff00);
+}
+
+#[test]
+fn same_legal_moves_as_board_state() {
+    let fen = "r3k2r/8/8/8/2r5/8/8/R3K2R w KQkq - 0 1";
+    let board = fen::BoardState::from_fen(fen).unwrap();
+    let position = Position::from_fen(fen).unwrap();
+    assert!(utils::equal_moves(
+        &board.legal_moves(),
+        &position.legal_moves()
+    ));
+}
+
+#[test]
+fn game_board_state() {
+    let fen = "r3k2r/8/8/8/2r5/8/8/R3K2R w KQkq - 0 1";
+    let game = chesslib::Game::start_from_fen(fen);
+    let board = game.board_state();
+    assert_eq!(board.to_fen(), fen);
+    assert!(utils::equal_moves(
+        &board.legal_moves(),
+        &game.legal_moves()
+    ));
+}
+
+#[test]
+fn transpositions_hash_equal() {
+    let mut first =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let mut second = first.clone();
+    for mov in ["g1f3", "g8f6", "b1c3"] {
+        first.make_move(&Move::from_long_algebraic(
+            mov.to_string(),
+            &fen::BoardState::from(&first),
+        ));
+    }
+    for mov in ["b1c3", "g8f6", "g1f3"] {
+        second.make_move(&Move::from_long_algebraic(
+            mov.to_string(),
+            &fen::BoardState::from(&second),
+        ));
+    }
+    assert_eq!(first.hash(), second.hash());
+    // the incrementally updated hash matches one computed from scratch
+    assert_eq!(
+        first.hash(),
+        Position::from_fen(&first.to_fen()).unwrap().hash()
+    );
+}
+
+#[test]
+fn hash_covers_side_castling_and_en_passant() {
+    let hash = |fen: &str| Position::from_fen(fen).unwrap().hash();
+    let base = hash("4k3/8/8/3pP3/8/8/8/R3K3 w Q d6 0 1");
+    assert_ne!(base, hash("4k3/8/8/3pP3/8/8/8/R3K3 b Q d6 0 1"));
+    assert_ne!(base, hash("4k3/8/8/3pP3/8/8/8/R3K3 w - d6 0 1"));
+    assert_ne!(base, hash("4k3/8/8/3pP3/8/8/8/R3K3 w Q - 0 1"));
+    // no pawn can capture en passant, so the square does not matter
+    assert_eq!(
+        hash("4k3/8/8/3p4/8/8/8/R3K3 w Q d6 0 1"),
+        hash("4k3/8/8/3p4/8/8/8/R3K3 w Q - 0 1")
+    );
+}
@@ -0,0 +1,96 @@
+#![allow(clippy::needless_return)]
+
+use chesslib::move_utils::*;
+use chesslib::square_utils::square_from_string;
+
+fn san_of(fen: &str, long_algebraic: &str) -> String {
+    let board = fen::BoardState::from_fen(fen).unwrap();
+    let mov = Move::from_long_algebraic(long_algebraic.to_string(), &board);
+    return mov.to_san(&board);
+}
+
+fn move_of(fen: &str, san: &str) -> Option<String> {
+    let board = fen::BoardState::from_fen(fen).unwrap();
+    return Move::from_san(san, &board).map(|mov| mov.to_long_algebraic());
+}
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const ROOKS: &str = "4k3/8/8/R6R/8/8/8/R3K3 w Q - 0 1";
+
+#[test]
+fn pawn_and_piece_moves() {
+    assert_eq!(san_of(START, "e2e4"), "e4");
+    assert_eq!(san_of(START, "g1f3"), "Nf3");
+}
+
+#[test]
+fn captures() {
+    let fen = "4k3/8/8/3p4/4P3/2N5/8/4K3 w - - 0 1";
+    assert_eq!(san_of(fen, "e4d5"), "exd5");
+    assert_eq!(san_of(fen, "c3d5"), "Nxd5");
+    assert_eq!(san_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+}
+
+#[test]
+fn disambiguation() {
+    // the rooks on a5 and h5 share a rank, a5 and a1 share a file
+    assert_eq!(san_of(ROOKS, "h5d5"), "Rhd5");
+    assert_eq!(san_of(ROOKS, "a1a3"), "R1a3");
+    assert_eq!(san_of(ROOKS, "a5a3"), "R5a3");
+    assert_eq!(san_of(ROOKS, "a1b1"), "Rb1");
+    let queens = "k7/8/8/8/8/2Q1Q3/8/2Q1K3 w - - 0 1";
+    assert_eq!(san_of(queens, "c3d2"), "Qc3d2");
+}
+
+#[test]
+fn castling_promotion_and_check() {
+    assert_eq!(san_of("r3k3/8/8/8/8/8/8/R3K2R w KQq - 0 1", "e1g1"), "O-O");
+    assert_eq!(
+        san_of("r3k3/8/8/8/8/8/8/R3K2R w KQq - 0 1", "e1c1"),
+        "O-O-O"
+    );
+    assert_eq!(san_of("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q+");
+    assert_eq!(san_of("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n"), "b8=N");
+    assert_eq!(san_of("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8#");
+}
+
+#[test]
+fn parse_san() {
+    assert_eq!(move_of(START, "e4").as_deref(), Some("e2e4"));
+    assert_eq!(move_of(START, "Nf3").as_deref(), Some("g1f3"));
+    assert_eq!(move_of(ROOKS, "Rhd5").as_deref(), Some("h5d5"));
+    assert_eq!(move_of(ROOKS, "R1a3").as_deref(), Some("a1a3"));
+    assert_eq!(move_of(ROOKS, "O-O-O").as_deref(), Some("e1c1"));
+    assert_eq!(
+        move_of("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=Q+").as_deref(),
+        Some("b7b8q")
+    );
+    let board = fen::BoardState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    let mov = Move::from_san("exd6", &board).unwrap();
+    assert!(mov.is_en_passant && mov.end_square == square_from_string("d6"));
+}
+
+#[test]
+fn parse_forgiving_san() {
+    assert_eq!(move_of(START, "Ng1-f3!?").as_deref(), Some("g1f3"));
+    assert_eq!(move_of(START, "Ngf3").as_deref(), Some("g1f3"));
+    assert_eq!(move_of(ROOKS, "0-0-0").as_deref(), Some("e1c1"));
+    assert_eq!(
+        move_of("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8Q").as_deref(),
+        Some("b7b8q")
+    );
+    assert_eq!(
+        move_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6 e.p.").as_deref(),
+        Some("e5d6")
+    );
+}
+
+#[test]
+fn parse_rejects_ambiguous_and_illegal() {
+    assert_eq!(move_of(ROOKS, "Rd5"), None);
+    assert_eq!(move_of(ROOKS, "Ra3"), None);
+    assert_eq!(move_of(START, "e5"), None);
+    assert_eq!(move_of(START, "O-O"), None);
+    assert_eq!(move_of(START, "Qh9"), None);
+    assert_eq!(move_of(START, ""), None);
+}
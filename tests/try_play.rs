@@ -0,0 +1,58 @@
+#![allow(clippy::needless_return)]
+
+use chesslib::{Color, Game, IllegalMove, Piece, PieceKind};
+
+mod utils;
+
+use utils::mov;
+
+#[test]
+fn rejects_with_reason() {
+    let mut game = Game::start_from_fen("r3k3/1P6/8/8/8/4r3/3rN3/R3K2R w KQ - 0 1");
+    let cases = [
+        ("d4d5", IllegalMove::EmptySquare),
+        ("e3e4", IllegalMove::NotYourTurn),
+        ("a1h1", IllegalMove::CapturesOwnPiece),
+        ("a1b2", IllegalMove::InvalidMovement),
+        ("e1c1", IllegalMove::CannotCastle),
+        ("b7b8", IllegalMove::InvalidPromotion),
+        ("e2c3", IllegalMove::LeavesKingInCheck),
+    ];
+    for (long_algebraic, reason) in cases {
+        assert_eq!(game.try_play(&mov(long_algebraic)), Err(reason));
+    }
+    assert_eq!(game.moves().len(), 0);
+}
+
+#[test]
+fn reports_outcome() {
+    let mut game = Game::start_from_fen("r3k3/1P6/8/8/8/4r3/3rN3/R3K2R w KQ - 0 1");
+    let outcome = game.try_play(&mov("e1d2")).unwrap();
+    assert_eq!(
+        outcome.captured,
+        Some(Piece {
+            kind: PieceKind::Rook,
+            color: Color::Black
+        })
+    );
+    assert!(!outcome.check && !outcome.checkmate && !outcome.draw);
+
+    let mut game = Game::start_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let outcome = game.try_play(&mov("a1a8")).unwrap();
+    assert!(outcome.check && outcome.checkmate && outcome.captured.is_none());
+}
+
+#[test]
+fn takes_flags_from_legal_move() {
+    let mut game = Game::start_from_fen("4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 1");
+    let outcome = game.try_play(&mov("e5d6")).unwrap();
+    assert!(outcome.played.is_en_passant);
+    assert_eq!(
+        outcome.captured.map(|piece| piece.kind),
+        Some(PieceKind::Pawn)
+    );
+    game.try_play(&mov("e8d8")).unwrap();
+    let outcome = game.try_play(&mov("e1g1")).unwrap();
+    assert!(outcome.played.castles);
+    assert_eq!(game.board.to_fen(), "3k4/8/3P4/8/8/8/8/5RK1 b - - 2 2");
+}
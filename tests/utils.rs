@@ -1,11 +1,38 @@
+#![allow(clippy::needless_return)]
+
 use chesslib::move_generation::Move;
+use chesslib::square_utils::square_from_string;
+use chesslib::{Game, PieceKind};
+
+// the move given in long algebraic notation, e.g. "e2e4", a fifth
+// character promotes to a queen
+#[allow(dead_code)]
+pub fn mov(long_algebraic: &str) -> Move {
+    let mut mov = Move::standard(
+        square_from_string(&long_algebraic[0..2]),
+        square_from_string(&long_algebraic[2..4]),
+    );
+    if long_algebraic.len() == 5 {
+        mov.promotion = Some(PieceKind::Queen);
+    }
+    return mov;
+}
+
+// plays the moves given in SAN by the rules of the game's variant
+#[allow(dead_code)]
+pub fn play(game: &mut Game, san: &[&str]) {
+    for san in san {
+        let mov = game.variant().move_from_san(&game.board, san).unwrap();
+        game.try_play(&mov).unwrap();
+    }
+}
 
 // returns true iff the superset contains all moves provided in moves
 #[allow(dead_code)]
-pub fn contains_moves(superset: &Vec<Move>, moves: &Vec<Move>) -> bool {
+pub fn contains_moves(superset: &[Move], moves: &[Move]) -> bool {
     let mut success = true;
     for mov in moves {
-        if !superset.contains(&mov) {
+        if !superset.contains(mov) {
             success = false;
         }
     }
@@ -14,19 +41,19 @@ pub fn contains_moves(superset: &Vec<Move>, moves: &Vec<Move>) -> bool {
 
 // returns true iff the superset contains all moves provided in moves
 #[allow(dead_code)]
-pub fn equal_moves(expected: &Vec<Move>, moves: &Vec<Move>) -> bool {
+pub fn equal_moves(expected: &[Move], moves: &[Move]) -> bool {
     return contains_moves(expected, moves) && contains_moves(moves, expected);
 }
 
 // return true iff no move in the given list of moves starts at the provided square
 #[allow(dead_code)]
-pub fn no_move_starting_at(square: usize, moves: &Vec<Move>) -> bool {
+pub fn no_move_starting_at(square: usize, moves: &[Move]) -> bool {
     return !moves.iter().any(|m| m.start_square == square);
 }
 
 // return true iff exactly the expected moves starting from square are present in move
 #[allow(dead_code)]
-pub fn moves_starting_from(square: usize, expected: &Vec<Move>, moves: &Vec<Move>) -> bool {
+pub fn moves_starting_from(square: usize, expected: &[Move], moves: &[Move]) -> bool {
     return moves
         .iter()
         .all(|m| m.start_square != square || expected.contains(m));
@@ -0,0 +1,85 @@
+#![allow(clippy::needless_return)]
+
+use chesslib::bitboard::{contains, square_bb};
+use chesslib::end_state::GameStatus;
+use chesslib::move_utils::{Move, ToggleColor};
+use chesslib::pgn::{read_game, PgnError};
+use chesslib::position::Position;
+use chesslib::square_utils::square_from_string;
+use chesslib::variant::{self, Standard, Variant};
+use chesslib::{Color, Game, IllegalMove, PieceKind};
+use std::sync::Arc;
+
+mod utils;
+
+use utils::mov;
+
+// A king reaching one of the four center squares wins the game
+struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        return "King of the Hill";
+    }
+
+    fn outcome(&self, position: &Position, legal_moves: &[Move]) -> Option<GameStatus> {
+        let center = ["d4", "e4", "d5", "e5"]
+            .iter()
+            .fold(0, |all, square| all | square_bb(square_from_string(square)));
+        let opponent = position.side_to_play().inverse_color();
+        if position.pieces(&opponent, &PieceKind::King) & center != 0 {
+            return Some(GameStatus::Checkmate(opponent));
+        }
+        // a bare king can still walk to the center
+        return match Standard.outcome(position, legal_moves) {
+            Some(GameStatus::Insufficient) => None,
+            outcome => outcome,
+        };
+    }
+}
+
+#[test]
+fn standard_is_the_default() {
+    let game = Game::new();
+    assert_eq!(game.variant().name(), "Standard");
+    let standard = Game::with_variant(Arc::new(Standard));
+    assert_eq!(standard.board.to_fen(), game.board.to_fen());
+    assert_eq!(standard.legal_moves().len(), 20);
+}
+
+#[test]
+fn variant_decides_the_outcome() {
+    let fen = "4k3/8/8/8/8/4K3/8/8 w - - 0 1";
+    let mut game = Game::try_variant_from_fen(Arc::new(KingOfTheHill), fen).unwrap();
+    // two bare kings are a draw in standard chess but not on the hill
+    assert_eq!(Game::start_from_fen(fen).status(), GameStatus::Insufficient);
+    assert_eq!(game.status(), GameStatus::Ongoing);
+
+    game.try_play(&mov("e3e4")).unwrap();
+    assert_eq!(game.status(), GameStatus::Checkmate(Color::White));
+    assert!(contains(
+        game.board.pieces(&Color::White, &PieceKind::King),
+        square_from_string("e4")
+    ));
+    assert_eq!(game.try_play(&mov("e8e7")), Err(IllegalMove::GameOver));
+}
+
+#[test]
+fn variant_by_name() {
+    assert_eq!(variant::from_name("Standard").unwrap().name(), "Standard");
+    assert_eq!(variant::from_name("chess960").unwrap().name(), "Standard");
+    assert!(variant::from_name("Losers").is_none());
+}
+
+#[test]
+fn pgn_variant_tag() {
+    let game = Game::with_variant(Arc::new(KingOfTheHill));
+    assert!(game.to_pgn().contains("[Variant \"King of the Hill\"]\n"));
+    assert!(!Game::new().to_pgn().contains("[Variant "));
+
+    let pgn = "[Variant \"Losers\"]\n\n1. e4 *";
+    assert!(matches!(
+        read_game(pgn),
+        Err(PgnError::UnsupportedVariant(name)) if name == "Losers"
+    ));
+}